@@ -9,6 +9,8 @@ use bevy_seedling::{
 };
 use firewheel_phonon::effects::spatializer::SpatializerNode;
 
+use crate::VoiceOf;
+
 /// The [`GizmoConfigGroup`] used to configure the visualization of audio entities/geometry.
 #[derive(Clone, Reflect, GizmoConfigGroup)]
 #[reflect(Clone, Default)]
@@ -42,9 +44,12 @@ impl Default for AudioGizmoConfigGroup {
     }
 }
 
+/// Sample players, without the voices the plugin plays of them for other listeners.
+type SourceFilter = (With<SamplePlayer>, Without<VoiceOf>);
+
 pub(crate) fn visualize_sources(
     mut gizmos: Gizmos<AudioGizmoConfigGroup>,
-    audio_sources: Query<(&SampleEffects, &GlobalTransform), With<SamplePlayer>>,
+    audio_sources: Query<(&SampleEffects, &GlobalTransform), SourceFilter>,
     effects: Query<&SpatializerNode>,
 ) -> Result {
     for (player, transform) in audio_sources {
@@ -12,7 +12,98 @@ pub mod prelude {
 }
 
 use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::resource::Resource;
+use bevy_seedling::prelude::PoolLabel;
 pub use firewheel_phonon::effects;
+use firewheel_phonon::phonon::effects::spread::spread_from_radius;
+use firewheel_phonon::phonon::models::directivity::DirectivityModel;
+use firewheel_phonon::phonon::models::occlusion::OcclusionModel;
+use firewheel_phonon::phonon::models::propagation_medium::PropagationMedium;
+use firewheel_phonon::phonon::models::transmission::TransmissionModel;
+use firewheel_phonon::phonon::simulators::source_shape::{ShapeAttenuation, SourceShape};
+use std::sync::Arc;
 
+/// A point sound is heard from. There can be several, for example one per player in a
+/// split-screen game, and every listener renders its own mix.
+///
+/// Sample players are spatialized for the first listener, the one that was added first, in the
+/// pool they are played in. For every other listener the plugin spawns a sampler pool
+/// labeled [`ListenerPool`], and plays a voice of every source in it that is spatialized for
+/// that listener. The pools are routed to the main bus. Reroute a pool to give its listener a
+/// separate output, such as the headphones of one of the players.
 #[derive(Component)]
 pub struct AudioListener;
+
+/// Label of the sampler pool that renders the mix of an [`AudioListener`] other than the first.
+#[derive(PoolLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ListenerPool(pub Entity);
+
+/// A copy of a source that the plugin plays in a [`ListenerPool`]. It follows the playback
+/// settings of the source, and is despawned with it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[relationship(relationship_target = ListenerVoices)]
+pub struct VoiceOf(pub Entity);
+
+/// The voices of a source, one for every [`AudioListener`] but the first.
+#[derive(Component, Debug)]
+#[relationship_target(relationship = VoiceOf, linked_spawn)]
+pub struct ListenerVoices(Vec<Entity>);
+
+/// The medium all sound travels through. It determines the propagation delay and the air
+/// absorption of every source. Change it at runtime, for example when the listener dives
+/// under water.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Default)]
+pub struct AudioMedium(pub PropagationMedium);
+
+/// Gives the [`AudioListener`] a size, in meters. Sources with volumetric occlusion then sample
+/// the volume around the listener as well, which suits large listeners such as vehicles.
+/// Without this component the listener is a single point.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct AudioListenerRadius(pub f32);
+
+/// Replaces the dipole directivity of the spatializer settings of a source, for patterns such
+/// as cones or measured balloons.
+#[derive(Component, Clone)]
+pub struct SourceDirectivity(pub Arc<dyn DirectivityModel + Send + Sync>);
+
+/// Replaces the ray traced occlusion of a source, for example with queries against a physics
+/// engine or a gameplay override for a closed door.
+#[derive(Component, Clone)]
+pub struct SourceOcclusionModel(pub Arc<dyn OcclusionModel + Send + Sync>);
+
+/// Replaces the ray traced transmission of a source, for example with designer-tuned values.
+#[derive(Component, Clone)]
+pub struct SourceTransmissionModel(pub Arc<dyn TransmissionModel + Send + Sync>);
+
+/// Gives a source an extent, such as a line for a river or a rectangle for a waterfall. The
+/// source is then simulated from its point closest to the listener, its occlusion is the visible
+/// fraction of the shape, and it is spatialized towards the visible part.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct AudioSourceShape(pub SourceShape);
+
+/// How distance attenuation and air absorption are evaluated for a source with an
+/// [`AudioSourceShape`]. Without this component the point closest to the listener is used.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct AudioShapeAttenuation(pub ShapeAttenuation);
+
+/// Widens the rendered image of a source, so that a large source close to the listener fills
+/// the sound field instead of collapsing to a point.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum AudioSourceSpread {
+    /// A fixed spread in degrees, from 0 for a point up to 360 for a source that surrounds
+    /// the listener.
+    Angle(f32),
+    /// The radius of the source in meters. The spread grows as the listener gets closer.
+    Radius(f32),
+}
+
+impl AudioSourceSpread {
+    /// Returns the spread in degrees for a listener at `distance` meters.
+    pub fn angle(&self, distance: f32) -> f32 {
+        match *self {
+            AudioSourceSpread::Angle(angle) => angle,
+            AudioSourceSpread::Radius(radius) => spread_from_radius(radius, distance),
+        }
+    }
+}
@@ -1,19 +1,47 @@
 use crate::phonon_mesh::instancing::StaticMeshes;
-use crate::{AudioListener, phonon_mesh};
+use crate::{
+    AudioListener, AudioListenerRadius, AudioMedium, AudioShapeAttenuation, AudioSourceShape,
+    AudioSourceSpread, ListenerPool, ListenerVoices, SourceDirectivity, SourceOcclusionModel,
+    SourceTransmissionModel, VoiceOf, phonon_mesh,
+};
+use bevy::math::Affine3A;
 use bevy::prelude::*;
-use bevy_seedling::prelude::{EffectsQuery, SampleEffects};
-use bevy_seedling::sample::SamplePlayer;
+use bevy_seedling::pool::Sampler;
+use bevy_seedling::prelude::{EffectsQuery, OnComplete, PlayFrom, SampleEffects, SamplerPool};
+use bevy_seedling::sample::{PlaybackSettings, SamplePlayer};
+use bevy_seedling::sample_effects;
 use firewheel_phonon::effects::spatializer::SpatializerNode;
 use firewheel_phonon::phonon;
-use firewheel_phonon::phonon::models::air_absorption::DefaultAirAbsorptionModel;
-use firewheel_phonon::phonon::models::distance_attenuation::DefaultDistanceAttenuationModel;
+use firewheel_phonon::phonon::models::directivity::{Directivity, DirectivityModel};
+use firewheel_phonon::phonon::models::occlusion::OcclusionModel;
+use firewheel_phonon::phonon::models::transmission::TransmissionModel;
 use firewheel_phonon::phonon::scene::coordinate_space::CoordinateSpace3f;
-use firewheel_phonon::phonon::simulators::direct::{DirectSimulator, DirectSoundPath};
+use firewheel_phonon::phonon::simulators::simulator::{
+    CacheTolerances, Listener, ListenerResult, Simulator, Source, SourceSettings,
+};
+use firewheel_phonon::phonon::simulators::source_shape::SourceShape;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
-#[derive(Resource)]
+/// The simulator of the plugin, with the `Source` of every audio source entity.
+#[derive(Resource, Deref, DerefMut)]
 pub(crate) struct SteamSimulation {
-    pub(crate) simulator: DirectSimulator,
-    pub(crate) scene: phonon::scene::Scene,
+    #[deref]
+    pub(crate) simulator: Simulator,
+    sources: HashMap<Entity, SimulatedSource>,
+    /// Listener entities in the order they were added, which is also the order of the listeners
+    /// of the `Simulator`. The first one hears the sample players themselves.
+    listener_entities: Vec<Entity>,
+}
+
+/// An audio source entity as known to the `Simulator`.
+struct SimulatedSource {
+    source: Arc<Mutex<Source>>,
+    /// Models the source was last given, to notice when they are replaced or removed.
+    directivity: Option<Arc<dyn DirectivityModel + Send + Sync>>,
+    settings_directivity: Directivity,
+    occlusion_model: Option<Arc<dyn OcclusionModel + Send + Sync>>,
+    transmission_model: Option<Arc<dyn TransmissionModel + Send + Sync>>,
 }
 
 pub struct PhononPlugin {
@@ -21,97 +49,435 @@ pub struct PhononPlugin {
     /// occlusion is enabled on a `PhononSource`.
     /// This only sets the max, the actual amount is set per source
     pub max_occlusion_samples: usize,
+    /// Initial value of the [`AudioMedium`] resource.
+    pub medium: AudioMedium,
+    /// How far sources and listeners may move before a source is simulated again. `None`
+    /// simulates every source on every update.
+    pub cache_tolerances: Option<CacheTolerances>,
 }
 
 impl Default for PhononPlugin {
     fn default() -> Self {
         PhononPlugin {
             max_occlusion_samples: 128,
+            medium: AudioMedium::default(),
+            cache_tolerances: Some(CacheTolerances::default()),
         }
     }
 }
 
 impl Plugin for PhononPlugin {
     fn build(&self, app: &mut App) {
-        // This is the main scene to which all the geometry will be added
-        let scene = phonon::scene::Scene::new();
-
-        let simulator = DirectSimulator::new(self.max_occlusion_samples);
+        // The simulator owns the main scene, to which all the geometry will be added
+        let mut simulator = Simulator::new(self.max_occlusion_samples);
+        simulator.cache_tolerances = self.cache_tolerances;
 
-        app.insert_resource(SteamSimulation { simulator, scene })
-            .insert_resource(StaticMeshes::default())
-            //.register_type::<PhononSource>() todo
-            .add_systems(
-                Update,
+        app.insert_resource(SteamSimulation {
+            simulator,
+            sources: HashMap::new(),
+            listener_entities: Vec::new(),
+        })
+        .insert_resource(self.medium)
+        .insert_resource(StaticMeshes::default())
+        //.register_type::<PhononSource>() todo
+        .add_systems(
+            Update,
+            (
                 (
-                    (
-                        phonon_mesh::register_audio_meshes,
-                        phonon_mesh::update_audio_mesh_transforms,
-                    ),
-                    update_steam_audio,
-                )
-                    .chain(),
+                    phonon_mesh::register_audio_meshes,
+                    phonon_mesh::update_audio_mesh_transforms,
+                ),
+                sync_listener_voices,
+                update_steam_audio,
             )
-            .add_observer(phonon_mesh::on_remove_mesh);
+                .chain(),
+        )
+        .add_observer(phonon_mesh::on_remove_mesh);
+    }
+}
+
+/// A listener as seen by the plugin, in the same order as the listeners of the `Simulator`.
+struct ListenerInfo {
+    entity: Entity,
+    world_to_listener: Affine3A,
+}
+
+/// What a listener is simulated with.
+type ListenerData = (
+    Entity,
+    &'static GlobalTransform,
+    Option<&'static AudioListenerRadius>,
+);
+
+/// What the simulation of a source depends on.
+type SourceData = (
+    Entity,
+    &'static GlobalTransform,
+    &'static SampleEffects,
+    Option<&'static SourceDirectivity>,
+    Option<&'static AudioSourceShape>,
+    Option<&'static AudioShapeAttenuation>,
+    Option<&'static SourceOcclusionModel>,
+    Option<&'static SourceTransmissionModel>,
+);
+
+/// What the results of a source are rendered with.
+type SpatializerData = (
+    Entity,
+    &'static SampleEffects,
+    Option<&'static AudioSourceSpread>,
+    Option<&'static ListenerVoices>,
+);
+
+/// A source as seen by `sync_listener_voices`.
+type VoiceSourceData = (
+    Entity,
+    &'static SamplePlayer,
+    Ref<'static, PlaybackSettings>,
+    &'static SampleEffects,
+    Option<&'static ListenerVoices>,
+    Option<&'static Sampler>,
+);
+
+/// Gives every listener but the first a sampler pool, and plays a voice of every source in it.
+fn sync_listener_voices(
+    mut commands: Commands,
+    mut sim_res: ResMut<SteamSimulation>,
+    listener_query: Query<Entity, With<AudioListener>>,
+    pools: Query<(Entity, &SamplerPool<ListenerPool>)>,
+    sources: Query<VoiceSourceData, (With<SamplePlayer>, Without<VoiceOf>)>,
+    mut voices: Query<(&ListenerPool, &mut PlaybackSettings), With<VoiceOf>>,
+    spatializer_nodes: Query<&SpatializerNode>,
+) {
+    let listeners = &mut sim_res.listener_entities;
+    listeners.retain(|&listener| listener_query.contains(listener));
+    for listener in &listener_query {
+        if !listeners.contains(&listener) {
+            listeners.push(listener);
+        }
+    }
+    let others = listeners.get(1..).unwrap_or_default();
+
+    for (entity, pool) in &pools {
+        if !others.contains(&pool.0.0) {
+            commands.entity(entity).despawn();
+        }
+    }
+    for &listener in others {
+        if !pools.iter().any(|(_, pool)| pool.0.0 == listener) {
+            commands.spawn((
+                SamplerPool(ListenerPool(listener)),
+                sample_effects![SpatializerNode::default()],
+            ));
+        }
+    }
+
+    for (source, player, playback, effects, source_voices, sampler) in &sources {
+        let Ok(spatializer) = spatializer_nodes.get_effect(effects) else {
+            continue;
+        };
+
+        // A voice that finishes is kept, so that it is not spawned again. It is despawned with
+        // the source instead.
+        let voice_playback = |play_from| PlaybackSettings {
+            play_from,
+            on_complete: OnComplete::Preserve,
+            ..playback.clone()
+        };
+
+        let mut missing = others.to_vec();
+        for voice in source_voices.into_iter().flat_map(|voices| voices.iter()) {
+            let Ok((pool, mut settings)) = voices.get_mut(voice) else {
+                continue;
+            };
+
+            match missing.iter().position(|&listener| listener == pool.0) {
+                Some(index) => {
+                    missing.swap_remove(index);
+                    if playback.is_changed() {
+                        *settings = voice_playback(playback.play_from);
+                    }
+                }
+                None => commands.entity(voice).despawn(),
+            }
+        }
+
+        // New voices start where the source is, so that they play in sync.
+        let play_from = sampler
+            .and_then(Sampler::try_playhead_seconds)
+            .map_or(playback.play_from, |playhead| PlayFrom::Seconds(playhead.0));
+
+        for listener in missing {
+            commands.spawn((
+                VoiceOf(source),
+                ListenerPool(listener),
+                player.clone(),
+                voice_playback(play_from),
+                sample_effects![spatializer.clone()],
+            ));
+        }
     }
 }
 
 fn update_steam_audio(
     mut sim_res: ResMut<SteamSimulation>,
-    listener_query: Query<&GlobalTransform, With<AudioListener>>,
-    mut audio_sources: Query<(&GlobalTransform, &mut SampleEffects), With<SamplePlayer>>,
+    medium: Res<AudioMedium>,
+    listener_query: Query<ListenerData, With<AudioListener>>,
+    audio_sources: Query<SourceData, (With<SamplePlayer>, Without<VoiceOf>)>,
+    spatializer_query: Query<SpatializerData, (With<SamplePlayer>, Without<VoiceOf>)>,
+    voice_query: Query<(&ListenerPool, &SampleEffects), With<VoiceOf>>,
     mut spatializer_nodes: Query<&mut SpatializerNode>,
 ) {
-    // Commit changes to the sources, listener and scene.
-    sim_res.scene.commit();
+    let sim = &mut *sim_res;
+
+    if medium.is_changed() {
+        sim.simulator.set_medium(medium.0);
+    }
+
+    let listeners: Vec<_> = sim
+        .listener_entities
+        .iter()
+        .filter_map(|&listener| listener_query.get(listener).ok())
+        .collect();
 
-    let Ok(listener_transform) = listener_query.single() else {
+    if listeners.is_empty() {
         warn_once!("No audio listener was found");
         return;
-    };
-
-    let listener_position = CoordinateSpace3f::from_vectors(
-        listener_transform.forward().into(),
-        listener_transform.up().into(),
-        listener_transform.translation(),
-    );
-
-    for (source_transform, effects) in audio_sources.iter_mut() {
-        // todo remove unwrap
-        let mut effect = spatializer_nodes.get_effect_mut(&effects).unwrap();
-        let flags = effect.direct_effect_parameters.flags;
+    }
+
+    sim.simulator.listeners = listeners
+        .iter()
+        .map(|(_, transform, radius)| Listener {
+            coordinates: CoordinateSpace3f::from_vectors(
+                transform.forward().into(),
+                transform.up().into(),
+                transform.translation(),
+            ),
+            radius: radius.map_or(0.0, |radius| radius.0),
+        })
+        .collect();
+
+    let listeners: Vec<ListenerInfo> = listeners
+        .into_iter()
+        .map(|(entity, transform, _)| ListenerInfo {
+            entity,
+            world_to_listener: transform.affine().inverse(),
+        })
+        .collect();
+
+    // Bring the sources of the simulator in line with the source entities.
+    let mut seen = HashSet::new();
+
+    for (
+        entity,
+        transform,
+        effects,
+        directivity,
+        shape,
+        shape_attenuation,
+        occlusion_model,
+        transmission_model,
+    ) in &audio_sources
+    {
+        // todo: Warn when a source has no spatializer?
+        let Ok(effect) = spatializer_nodes.get_effect(effects) else {
+            continue;
+        };
+        seen.insert(entity);
+
+        let simulated = sim.sources.entry(entity).or_insert_with(|| {
+            let source = Arc::new(Mutex::new(Source::default()));
+            sim.simulator.add_source(source.clone());
+
+            SimulatedSource {
+                source,
+                directivity: None,
+                settings_directivity: Directivity::default(),
+                occlusion_model: None,
+                transmission_model: None,
+            }
+        });
+
         let settings = effect.simulator_settings;
+        let mut source = simulated.source.lock().unwrap();
 
-        let source_position = CoordinateSpace3f::from_vectors(
-            source_transform.forward().into(),
-            source_transform.up().into(),
-            source_transform.translation(),
-        );
-        let direction = source_transform
-            .reparented_to(listener_transform)
-            .translation;
-
-        let mut direct_sound_path = DirectSoundPath::default();
-
-        sim_res.simulator.simulate(
-            Some(&sim_res.scene),
-            flags,
-            &source_position,
-            &listener_position,
-            &DefaultDistanceAttenuationModel::default(),
-            &DefaultAirAbsorptionModel::default(),
-            settings.directivity,
-            settings.occlusion_type,
-            settings.occlusion_radius,
-            settings.occlusion_samples,
-            settings.num_transmission_rays,
-            &mut direct_sound_path,
+        source.coordinates = CoordinateSpace3f::from_vectors(
+            transform.forward().into(),
+            transform.up().into(),
+            transform.translation(),
         );
+        source.settings = SourceSettings {
+            flags: effect.direct_effect_parameters.flags,
+            occlusion_type: settings.occlusion_type,
+            occlusion_radius: settings.occlusion_radius,
+            num_occlusion_samples: settings.occlusion_samples,
+            num_transmission_rays: settings.num_transmission_rays,
+            shape_attenuation: shape_attenuation
+                .map(|attenuation| attenuation.0)
+                .unwrap_or_default(),
+        };
+
+        let shape = shape.map_or(&SourceShape::Point, |shape| &shape.0);
+        if source.shape != *shape {
+            source.shape = shape.clone();
+        }
+
+        // The cache cannot see a change of model, so replacing one simulates the source again.
+        let directivity = directivity.map(|directivity| directivity.0.clone());
+        if !same_model(&simulated.directivity, &directivity)
+            || (directivity.is_none() && simulated.settings_directivity != settings.directivity)
+        {
+            source.directivity_model = match &directivity {
+                Some(directivity) => Box::new(SharedModel(directivity.clone())),
+                None => Box::new(settings.directivity),
+            };
+            source.invalidate_cache();
+            simulated.directivity = directivity;
+            simulated.settings_directivity = settings.directivity;
+        }
+
+        let occlusion_model = occlusion_model.map(|model| model.0.clone());
+        if !same_model(&simulated.occlusion_model, &occlusion_model) {
+            source.occlusion_model =
+                occlusion_model
+                    .clone()
+                    .map(|model| -> Box<dyn OcclusionModel + Send + Sync> {
+                        Box::new(SharedModel(model))
+                    });
+            source.invalidate_cache();
+            simulated.occlusion_model = occlusion_model;
+        }
+
+        let transmission_model = transmission_model.map(|model| model.0.clone());
+        if !same_model(&simulated.transmission_model, &transmission_model) {
+            source.transmission_model = transmission_model.clone().map(
+                |model| -> Box<dyn TransmissionModel + Send + Sync> {
+                    Box::new(SharedModel(model))
+                },
+            );
+            source.invalidate_cache();
+            simulated.transmission_model = transmission_model;
+        }
+    }
+
+    sim.sources.retain(|entity, simulated| {
+        let keep = seen.contains(entity);
+        if !keep {
+            sim.simulator.remove_source(simulated.source.clone());
+        }
+        keep
+    });
+
+    // Commit changes to the sources and the scene. Sources that did not change, relative to a
+    // listener that did not move either, keep their cached results.
+    sim.simulator.commit();
+    sim.simulator.run_direct();
+
+    for (entity, effects, spread, voices) in &spatializer_query {
+        let Some(simulated) = sim.sources.get(&entity) else {
+            continue;
+        };
+        let source = simulated.source.lock().unwrap();
+        let results = &source.listener_results;
+
+        // The sample player itself is heard by the first listener.
+        let Ok(mut effect) = spatializer_nodes.get_effect_mut(effects) else {
+            continue;
+        };
+        apply_result(&mut effect, &listeners[0], &results[0], spread);
+        let template = effect.clone();
+
+        // Its voices by the others. They follow the settings of the source.
+        for voice in voices.into_iter().flat_map(|voices| voices.iter()) {
+            let Ok((pool, voice_effects)) = voice_query.get(voice) else {
+                continue;
+            };
+            let Some(index) = listeners
+                .iter()
+                .position(|listener| listener.entity == pool.0)
+            else {
+                continue;
+            };
+            let Ok(mut effect) = spatializer_nodes.get_effect_mut(voice_effects) else {
+                continue;
+            };
+
+            *effect = template.clone();
+            apply_result(&mut effect, &listeners[index], &results[index], spread);
+        }
+    }
+}
+
+/// Sends the direct sound path of a source for one listener to a spatializer, and points it
+/// at where the source appears to be.
+fn apply_result(
+    effect: &mut SpatializerNode,
+    listener: &ListenerInfo,
+    result: &ListenerResult,
+    spread: Option<&AudioSourceSpread>,
+) {
+    let direction = listener
+        .world_to_listener
+        .transform_point3(result.apparent_position);
+
+    effect.direct_effect_parameters.direct_sound_path = result.direct_sound_path;
+    // Note the change in coordinate systems here
+    effect.binaural_effect_parameters.direction.x = direction.x;
+    effect.binaural_effect_parameters.direction.y = -direction.z;
+    effect.binaural_effect_parameters.direction.z = direction.y;
+    effect.binaural_effect_parameters.spread =
+        spread.map_or(0.0, |spread| spread.angle(direction.length()));
+}
+
+/// Whether a source still has the model it was given, or still has none.
+fn same_model<T: ?Sized>(current: &Option<Arc<T>>, new: &Option<Arc<T>>) -> bool {
+    match (current, new) {
+        (Some(current), Some(new)) => Arc::ptr_eq(current, new),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// A model shared with a component, handed to a `Source`.
+struct SharedModel<T: ?Sized>(Arc<T>);
+
+impl<T: DirectivityModel + ?Sized> DirectivityModel for SharedModel<T> {
+    fn evaluate(&self, direction: Vec3) -> f32 {
+        self.0.evaluate(direction)
+    }
+
+    fn evaluate_band(&self, direction: Vec3, band: usize) -> f32 {
+        self.0.evaluate_band(direction, band)
+    }
+
+    fn evaluate_at(&self, point: Vec3, coordinates: &CoordinateSpace3f) -> f32 {
+        self.0.evaluate_at(point, coordinates)
+    }
+
+    fn evaluate_bands_at(&self, point: Vec3, coordinates: &CoordinateSpace3f) -> [f32; 3] {
+        self.0.evaluate_bands_at(point, coordinates)
+    }
+}
+
+impl<T: OcclusionModel + ?Sized> OcclusionModel for SharedModel<T> {
+    fn evaluate(
+        &self,
+        scene: &phonon::scene::Scene,
+        listener: &CoordinateSpace3f,
+        source: &CoordinateSpace3f,
+    ) -> f32 {
+        self.0.evaluate(scene, listener, source)
+    }
+}
 
-        effect.direct_effect_parameters.direct_sound_path = direct_sound_path;
-        // Note the change in coordinate systems here
-        effect.binaural_effect_parameters.direction.x = direction.x;
-        effect.binaural_effect_parameters.direction.y = -direction.z;
-        effect.binaural_effect_parameters.direction.z = direction.y;
+impl<T: TransmissionModel + ?Sized> TransmissionModel for SharedModel<T> {
+    fn evaluate(
+        &self,
+        scene: &phonon::scene::Scene,
+        listener: &CoordinateSpace3f,
+        source: &CoordinateSpace3f,
+        transmission: &mut [f32; 3],
+    ) {
+        self.0.evaluate(scene, listener, source, transmission)
     }
 }
@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+use bevy_phonon::effects::spatializer::SpatializerNode;
+use bevy_phonon::prelude::PhononPlugin;
+use bevy_phonon::{AudioListener, ListenerPool, SourceOcclusionModel, VoiceOf};
+use bevy_seedling::prelude::{SampleEffects, SamplerPool};
+use bevy_seedling::sample::SamplePlayer;
+use bevy_seedling::sample_effects;
+use firewheel_phonon::phonon::models::occlusion::OcclusionModel;
+use firewheel_phonon::phonon::scene::Scene;
+use firewheel_phonon::phonon::scene::coordinate_space::CoordinateSpace3f;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts how often a source is simulated.
+#[derive(Default)]
+struct CountingOcclusion(AtomicUsize);
+
+impl OcclusionModel for CountingOcclusion {
+    fn evaluate(
+        &self,
+        _scene: &Scene,
+        _listener: &CoordinateSpace3f,
+        _source: &CoordinateSpace3f,
+    ) -> f32 {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        1.0
+    }
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        PhononPlugin::default(),
+    ))
+    .init_asset::<Mesh>();
+    app
+}
+
+/// The spatializer effect of a sample player or voice.
+fn spatializer(app: &mut App, player: Entity) -> SpatializerNode {
+    let world = app.world();
+    let effects = world.get::<SampleEffects>(player).unwrap();
+    effects
+        .iter()
+        .find_map(|effect| world.get::<SpatializerNode>(effect))
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn stationary_sources_are_not_simulated_again() {
+    let mut app = app();
+
+    let occlusion = Arc::new(CountingOcclusion::default());
+    let count = || occlusion.0.load(Ordering::Relaxed);
+
+    app.world_mut()
+        .spawn((AudioListener, GlobalTransform::default()));
+    let source = app
+        .world_mut()
+        .spawn((
+            SamplePlayer::new(Handle::default()),
+            sample_effects![SpatializerNode::default()],
+            GlobalTransform::from_xyz(0.0, 0.0, -5.0),
+            SourceOcclusionModel(occlusion.clone()),
+        ))
+        .id();
+
+    app.update();
+    assert_eq!(count(), 1);
+
+    app.update();
+    app.update();
+    assert_eq!(count(), 1);
+
+    // Moving the source simulates it again.
+    app.world_mut()
+        .entity_mut(source)
+        .insert(GlobalTransform::from_xyz(0.0, 0.0, -6.0));
+    app.update();
+    assert_eq!(count(), 2);
+
+    // So does replacing its occlusion model.
+    let replacement = Arc::new(CountingOcclusion::default());
+    app.world_mut()
+        .entity_mut(source)
+        .insert(SourceOcclusionModel(replacement.clone()));
+    app.update();
+    assert_eq!(count(), 2);
+    assert_eq!(replacement.0.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn every_listener_hears_its_own_voice() {
+    let mut app = app();
+
+    app.world_mut()
+        .spawn((AudioListener, GlobalTransform::from_xyz(0.0, 0.0, -4.0)));
+    let source = app
+        .world_mut()
+        .spawn((
+            SamplePlayer::new(Handle::default()),
+            sample_effects![SpatializerNode::default()],
+            GlobalTransform::from_xyz(0.0, 0.0, -5.0),
+        ))
+        .id();
+    app.update();
+
+    let far = app
+        .world_mut()
+        .spawn((AudioListener, GlobalTransform::from_xyz(0.0, 0.0, 20.0)))
+        .id();
+    app.update();
+
+    // The listener added first hears the source itself, the other one a voice in its own pool.
+    let mut voices = app.world_mut().query::<(Entity, &VoiceOf, &ListenerPool)>();
+    let voices: Vec<_> = voices
+        .iter(app.world())
+        .map(|(voice, voice_of, pool)| (voice, voice_of.0, pool.0))
+        .collect();
+    assert_eq!(voices.len(), 1);
+    let (voice, voice_of, pool) = voices[0];
+    assert_eq!((voice_of, pool), (source, far));
+
+    let mut pools = app.world_mut().query::<&SamplerPool<ListenerPool>>();
+    assert_eq!(pools.iter(app.world()).count(), 1);
+
+    let near_path = spatializer(&mut app, source)
+        .direct_effect_parameters
+        .direct_sound_path;
+    let far_path = spatializer(&mut app, voice)
+        .direct_effect_parameters
+        .direct_sound_path;
+    assert!(near_path.distance_attenuation > far_path.distance_attenuation);
+    assert!(near_path.delay < far_path.delay);
+
+    // Without the second listener, its voice and pool go away.
+    app.world_mut().despawn(far);
+    app.update();
+    app.update();
+
+    let mut voices = app.world_mut().query::<&VoiceOf>();
+    assert_eq!(voices.iter(app.world()).count(), 0);
+    let mut pools = app.world_mut().query::<&SamplerPool<ListenerPool>>();
+    assert_eq!(pools.iter(app.world()).count(), 0);
+
+    // And the remaining listener keeps hearing the source.
+    assert_eq!(
+        spatializer(&mut app, source)
+            .direct_effect_parameters
+            .direct_sound_path,
+        near_path
+    );
+}
@@ -2,6 +2,7 @@ use std::f32::consts::PI;
 
 use bevy::{color::palettes::css::RED, prelude::*};
 use bevy_phonon::effects::spatializer::SpatializerNode;
+use phonon::models::directivity::DirectivityModel;
 
 use crate::{
     DemoAssets,
@@ -97,6 +97,8 @@ fn main() {
         direct_sound_path: DirectSoundPath::default(),
         flags: DirectApplyFlags::none(),
         transmission_type: TransmissionType::FrequencyDependent,
+        smoothing: Default::default(),
+        doppler_scale: 1.0,
     };
     let mut binaural_params = BinauralEffectParameters::default();
 
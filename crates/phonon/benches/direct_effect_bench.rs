@@ -53,7 +53,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 delay: 0.0,
                 occlusion: 0.5,
                 transmission: [0.1, 0.2, 0.3],
-                directivity: 0.0,
+                directivity: [0.0, 0.0, 0.0],
+                diffraction: [0.0, 0.0, 0.0],
             },
             flags: DirectApplyFlags {
                 distance_attenuation: true,
@@ -62,8 +63,11 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 occlusion: true,
                 transmission: false,
                 delay: false,
+                diffraction: false,
             },
             transmission_type,
+            smoothing: Default::default(),
+            doppler_scale: 1.0,
         };
 
         direct_params.flags.transmission = apply_transmission;
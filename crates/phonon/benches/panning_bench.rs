@@ -47,7 +47,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         let out_ref = &mut out_buffer.as_ref_mut();
 
         b.iter(|| {
-            let panning_params = PanningEffectParameters { direction };
+            let panning_params = PanningEffectParameters {
+                direction,
+                spread: 0.0,
+            };
             panning_effect.apply(panning_params, in_ref, out_ref);
         })
     });
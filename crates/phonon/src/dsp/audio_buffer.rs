@@ -15,6 +15,7 @@
 // limitations under the License.
 //
 
+use crate::random::DEFAULT_SEED;
 use derive_deref::{Deref, DerefMut};
 
 pub enum AudioEffectState {
@@ -26,6 +27,8 @@ pub enum AudioEffectState {
 pub struct AudioSettings {
     pub sampling_rate: u32,
     pub frame_size: usize,
+    /// Seed for effects with randomized parts, such as the delay lines of the `ReverbEffect`.
+    pub seed: u64,
 }
 
 impl AudioSettings {
@@ -33,6 +36,7 @@ impl AudioSettings {
         Self {
             sampling_rate,
             frame_size,
+            seed: DEFAULT_SEED,
         }
     }
 }
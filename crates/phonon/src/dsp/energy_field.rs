@@ -0,0 +1,67 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::dsp::bands::NUM_BANDS;
+use ndarray::Array3;
+
+/// Duration of a single histogram bin, in seconds.
+pub const BIN_DURATION: f32 = 1e-2;
+
+/// A time-binned histogram of sound energy arriving at a point, with one histogram per frequency
+/// band. Optionally, the energy is also stored per Ambisonic channel, so that the directional
+/// distribution of the energy is retained.
+///
+/// Channel 0 always contains the omnidirectional energy. If the Ambisonic order is greater than 0,
+/// the remaining channels contain the projection of the energy onto the corresponding spherical
+/// harmonics, in ACN order.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnergyField {
+    /// Energy values, indexed by `[channel, band, bin]`.
+    pub data: Array3<f32>,
+}
+
+impl EnergyField {
+    /// Creates an energy field that is long enough to contain `duration` seconds of energy,
+    /// for the given Ambisonic `order`. All bins are initialized to zero.
+    pub fn new(duration: f32, order: usize) -> Self {
+        let num_channels = (order + 1) * (order + 1);
+        let num_bins = (duration / BIN_DURATION).ceil() as usize;
+
+        Self {
+            data: Array3::zeros((num_channels, NUM_BANDS, num_bins)),
+        }
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.data.dim().0
+    }
+
+    pub fn num_bins(&self) -> usize {
+        self.data.dim().2
+    }
+
+    /// Duration of the energy field, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.num_bins() as f32 * BIN_DURATION
+    }
+
+    /// Sets all bins to zero.
+    pub fn reset(&mut self) {
+        self.data.fill(0.0);
+    }
+}
@@ -0,0 +1,145 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+/// Delay line with a delay that is not limited to whole samples, and that can change over time.
+///
+/// Within a frame the delay moves linearly from its value at the end of the previous frame to the
+/// requested one, and samples in between are linearly interpolated. A delay that changes over
+/// time resamples the signal, which is exactly the Doppler shift of a moving source.
+pub struct FractionalDelay {
+    ring_buffer: Vec<f32>,
+    /// Position the next input sample is written to.
+    cursor: usize,
+    /// Delay at the end of the previous frame, in samples, or `None` before the first frame.
+    delay: Option<f32>,
+}
+
+impl FractionalDelay {
+    /// Creates a delay line that supports delays of up to `max_delay` samples.
+    pub fn new(max_delay: usize) -> Self {
+        Self {
+            // One extra sample to interpolate at the maximum delay, and one for the current input.
+            ring_buffer: vec![0.0; max_delay + 2],
+            cursor: 0,
+            delay: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.ring_buffer.fill(0.0);
+        self.cursor = 0;
+        self.delay = None;
+    }
+
+    /// Maximum delay in samples. Longer delays are clamped to this.
+    pub fn max_delay(&self) -> usize {
+        self.ring_buffer.len() - 2
+    }
+
+    /// Delay at the end of the previous frame, in samples, or `None` if nothing was processed
+    /// since the last reset.
+    pub fn delay(&self) -> Option<f32> {
+        self.delay
+    }
+
+    /// Writes `input` to the delay line and reads the delayed signal into `output`, while the
+    /// delay moves towards `delay` samples. On the first frame after a reset the delay starts
+    /// at `delay` straight away.
+    ///
+    /// `input` and `output` must have the same length.
+    pub fn apply(&mut self, delay: f32, input: &[f32], output: &mut [f32]) {
+        let target_delay = delay.clamp(0.0, self.max_delay() as f32);
+        let start_delay = self.delay.unwrap_or(target_delay);
+        let step = (target_delay - start_delay) / input.len() as f32;
+
+        let len = self.ring_buffer.len();
+
+        for (i, (&sample, out)) in input.iter().zip(output.iter_mut()).enumerate() {
+            self.ring_buffer[self.cursor] = sample;
+
+            let delay = start_delay + step * (i + 1) as f32;
+            let whole = delay.floor();
+            let fraction = delay - whole;
+
+            // Both offsets are at most `max_delay + 1`, so they stay within the ring buffer.
+            let newer = (self.cursor + len - whole as usize) % len;
+            let older = (newer + len - 1) % len;
+
+            *out = self.ring_buffer[newer] * (1.0 - fraction) + self.ring_buffer[older] * fraction;
+
+            self.cursor = (self.cursor + 1) % len;
+        }
+
+        self.delay = Some(target_delay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_sample_delay() {
+        let mut delay = FractionalDelay::new(8);
+        let mut output = [0.0; 6];
+
+        delay.apply(2.0, &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0], &mut output);
+        assert_eq!([0.0, 0.0, 1.0, 0.0, 0.0, 0.0], output);
+    }
+
+    #[test]
+    fn fractional_delay_interpolates() {
+        let mut delay = FractionalDelay::new(8);
+        let mut output = [0.0; 4];
+
+        delay.apply(1.25, &[1.0, 0.0, 0.0, 0.0], &mut output);
+        assert_eq!([0.0, 0.75, 0.25, 0.0], output);
+    }
+
+    #[test]
+    fn changing_delay_resamples() {
+        let mut delay = FractionalDelay::new(64);
+        let ramp: Vec<f32> = (0..16).map(|i| i as f32).collect();
+        let mut output = [0.0; 16];
+
+        delay.apply(10.0, &ramp, &mut output);
+
+        // The delay grows by half a sample per sample, so the ramp comes out at half the slope:
+        // the pitch of a source moving away at half the speed of sound.
+        let ramp: Vec<f32> = (16..32).map(|i| i as f32).collect();
+        delay.apply(18.0, &ramp, &mut output);
+
+        for pair in output.windows(2) {
+            assert!((pair[1] - pair[0] - 0.5).abs() < 1e-4);
+        }
+        assert!((output[15] - (31.0 - 18.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn delay_is_clamped() {
+        let mut delay = FractionalDelay::new(4);
+        let mut output = [0.0; 8];
+
+        delay.apply(
+            100.0,
+            &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            &mut output,
+        );
+        assert_eq!(Some(4.0), delay.delay());
+        assert_eq!([0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0], output);
+    }
+}
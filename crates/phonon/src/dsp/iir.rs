@@ -74,6 +74,30 @@ impl IIR {
         IIR(DirectForm1::<f32>::new(coefficients.unwrap()))
     }
 
+    /// Creates a low-pass filter (removes all frequencies above the cutoff).
+    pub fn new_low_pass(cutoff: f32, sample_rate: u32) -> Self {
+        let coefficients = Coefficients::<f32>::from_params(
+            Type::LowPass,
+            sample_rate.hz(),
+            cutoff.hz(),
+            Q_BUTTERWORTH_F32,
+        );
+
+        IIR(DirectForm1::<f32>::new(coefficients.unwrap()))
+    }
+
+    /// Creates a high-pass filter (removes all frequencies below the cutoff).
+    pub fn new_high_pass(cutoff: f32, sample_rate: u32) -> Self {
+        let coefficients = Coefficients::<f32>::from_params(
+            Type::HighPass,
+            sample_rate.hz(),
+            cutoff.hz(),
+            Q_BUTTERWORTH_F32,
+        );
+
+        IIR(DirectForm1::<f32>::new(coefficients.unwrap()))
+    }
+
     pub fn new_empty() -> Self {
         IIR(DirectForm1::<f32>::new(Coefficients {
             a1: 0.0,
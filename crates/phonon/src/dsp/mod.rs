@@ -3,6 +3,9 @@
 pub mod audio_buffer;
 pub mod bands;
 pub mod delay;
+pub mod energy_field;
+pub mod fractional_delay;
 pub mod iir;
+pub mod reconstructor;
 pub mod reverb_estimator;
 pub mod speaker_layout;
@@ -0,0 +1,188 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::dsp::audio_buffer::AudioBufferMut;
+use crate::dsp::bands::{HIGH_CUTOFF_FREQUENCIES, LOW_CUTOFF_FREQUENCIES, NUM_BANDS};
+use crate::dsp::energy_field::{BIN_DURATION, EnergyField};
+use crate::dsp::iir::{IIR, IIRFilterer};
+use crate::random::{DEFAULT_SEED, rng_from_seed};
+use ndarray::Array2;
+use rand::Rng;
+
+/// Turns an `EnergyField` into an impulse response that can be used for convolution reverb.
+///
+/// For each band, an independent band-limited noise signal is generated once. When reconstructing, the
+/// noise of each band is multiplied by an envelope that follows the energy histogram of that band,
+/// and the bands are summed. The envelope is linearly interpolated between bin centers, so that
+/// there are no discontinuities at bin boundaries.
+///
+/// If the energy field has more than one channel, an Ambisonic impulse response is produced.
+/// Every channel uses the same noise, scaled by the ratio between the energy in that channel and
+/// the omnidirectional energy in channel 0.
+pub struct Reconstructor {
+    sampling_rate: u32,
+    /// Band-limited noise with unit RMS, one row per band.
+    band_noise: Array2<f32>,
+}
+
+impl Reconstructor {
+    /// Creates a reconstructor that can produce impulse responses up to `max_duration` seconds.
+    pub fn new(max_duration: f32, sampling_rate: u32) -> Self {
+        Self::with_seed(max_duration, sampling_rate, DEFAULT_SEED)
+    }
+
+    /// Like `new`, but generates the noise from `seed`.
+    pub fn with_seed(max_duration: f32, sampling_rate: u32, seed: u64) -> Self {
+        let num_samples = (max_duration * sampling_rate as f32).ceil() as usize;
+
+        let mut rng = rng_from_seed(seed);
+        let mut band_noise = Array2::zeros((NUM_BANDS, num_samples));
+
+        for band in 0..NUM_BANDS {
+            let mut row = band_noise.row_mut(band);
+            let samples = row.as_slice_mut().unwrap();
+
+            // Each band gets its own noise. Filtering a shared noise signal would make the bands
+            // correlated, and the crossover filters would partially cancel each other out.
+            for sample in samples.iter_mut() {
+                *sample = rng.random_range(-1.0..1.0);
+            }
+
+            for filter in Self::band_filters(band, sampling_rate) {
+                IIRFilterer::new(filter).apply_self(samples);
+            }
+
+            // Normalize, so that the energy in each bin of the output matches the energy field.
+            let energy: f32 = samples.iter().map(|sample| sample * sample).sum();
+            let rms = (energy / num_samples.max(1) as f32).sqrt();
+            if rms > f32::MIN_POSITIVE {
+                for sample in samples.iter_mut() {
+                    *sample /= rms;
+                }
+            }
+        }
+
+        Self {
+            sampling_rate,
+            band_noise,
+        }
+    }
+
+    /// Maximum number of samples that can be reconstructed.
+    pub fn max_num_samples(&self) -> usize {
+        self.band_noise.ncols()
+    }
+
+    /// Reconstructs an impulse response from `energy_field` into `output`. The number of output
+    /// channels determines how many Ambisonic channels are reconstructed. Samples beyond the
+    /// duration of the energy field, or beyond `max_num_samples`, are left silent.
+    pub fn reconstruct(&self, energy_field: &EnergyField, output: &mut [&mut [f32]]) {
+        output.make_silent();
+
+        let samples_per_bin = BIN_DURATION * self.sampling_rate as f32;
+        let num_bins = energy_field.num_bins();
+        if num_bins == 0 {
+            return;
+        }
+
+        let num_channels = output.num_channels().min(energy_field.num_channels());
+        let num_samples = output
+            .num_samples()
+            .min(self.max_num_samples())
+            .min((num_bins as f32 * samples_per_bin).ceil() as usize);
+
+        for band in 0..NUM_BANDS {
+            let noise = self.band_noise.row(band);
+
+            for i in 0..num_samples {
+                // Position of this sample relative to the bin centers.
+                let position = ((i as f32 + 0.5) / samples_per_bin - 0.5).max(0.0);
+                let bin = (position as usize).min(num_bins - 1);
+                let next_bin = (bin + 1).min(num_bins - 1);
+                let weight = (position - bin as f32).min(1.0);
+
+                let interpolate = |channel: usize| {
+                    let current = energy_field.data[[channel, band, bin]];
+                    let next = energy_field.data[[channel, band, next_bin]];
+                    current + weight * (next - current)
+                };
+
+                let omni_energy = interpolate(0).max(0.0);
+                if omni_energy <= f32::MIN_POSITIVE {
+                    continue;
+                }
+
+                let amplitude = (omni_energy / samples_per_bin).sqrt() * noise[i];
+                output[0][i] += amplitude;
+
+                for channel in 1..num_channels {
+                    let ratio = (interpolate(channel) / omni_energy).clamp(-1.0, 1.0);
+                    output[channel][i] += ratio * amplitude;
+                }
+            }
+        }
+    }
+
+    /// Filters that isolate a single band, using the same crossover frequencies as the rest of
+    /// the band processing.
+    fn band_filters(band: usize, sampling_rate: u32) -> Vec<IIR> {
+        if band == 0 {
+            vec![IIR::new_low_pass(HIGH_CUTOFF_FREQUENCIES[0], sampling_rate)]
+        } else if band == NUM_BANDS - 1 {
+            vec![IIR::new_high_pass(
+                LOW_CUTOFF_FREQUENCIES[band],
+                sampling_rate,
+            )]
+        } else {
+            vec![
+                IIR::new_high_pass(LOW_CUTOFF_FREQUENCIES[band], sampling_rate),
+                IIR::new_low_pass(HIGH_CUTOFF_FREQUENCIES[band], sampling_rate),
+            ]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::audio_buffer::ScratchBuffer;
+    use ndarray::s;
+
+    #[test]
+    fn reconstructed_energy_matches_histogram() {
+        let sampling_rate = 48_000;
+        let duration = 0.5;
+
+        let mut energy_field = EnergyField::new(duration, 1);
+        energy_field.data.slice_mut(s![0, .., ..]).fill(1.0);
+        energy_field.data.slice_mut(s![3, .., ..]).fill(-0.5);
+
+        let reconstructor = Reconstructor::new(duration, sampling_rate);
+        let mut impulse_response = ScratchBuffer::new(4, reconstructor.max_num_samples());
+        reconstructor.reconstruct(&energy_field, &mut impulse_response.as_ref_mut());
+
+        let expected_energy = (NUM_BANDS * energy_field.num_bins()) as f32;
+        let energy: f32 = impulse_response[0].iter().map(|x| x * x).sum();
+        assert!((energy / expected_energy - 1.0).abs() < 0.1);
+
+        assert!(impulse_response[1].iter().all(|&x| x == 0.0));
+
+        for (omni, x) in impulse_response[0].iter().zip(impulse_response[3].iter()) {
+            assert!((x + 0.5 * omni).abs() < 1e-6);
+        }
+    }
+}
@@ -16,7 +16,11 @@
 //
 
 use crate::dsp::bands::NUM_BANDS;
+use crate::dsp::energy_field::{BIN_DURATION, EnergyField};
+use crate::models::propagation_medium::PropagationMedium;
 
+/// Per-band reverb times (RT60), in seconds. This is what drives the `ReverbEffect`.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Reverb {
     pub reverb_times: [f32; NUM_BANDS],
 }
@@ -28,3 +32,156 @@ impl Default for Reverb {
         }
     }
 }
+
+/// Statistical description of a room, as used by the classic reverb time formulas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoomProperties {
+    /// Volume of the room, in cubic meters.
+    pub volume: f32,
+    /// Total surface area of the room, in square meters.
+    pub surface_area: f32,
+    /// Area-weighted average absorption coefficient of the surfaces, per band.
+    pub absorption: [f32; NUM_BANDS],
+}
+
+/// Formula used to estimate reverb times from `RoomProperties`.
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReverbFormula {
+    /// Works well for rooms with little absorption (average absorption below ~0.2).
+    Sabine,
+    /// More accurate than Sabine for rooms with a lot of absorption.
+    Eyring,
+}
+
+/// Estimates reverb times, either from the geometry of a room or from an energy histogram.
+pub struct ReverbEstimator;
+
+impl ReverbEstimator {
+    /// Estimates reverb times using the Sabine or Eyring formula. The absorption of `medium` is
+    /// taken into account as an additional absorption term proportional to the room volume.
+    pub fn estimate_from_room(
+        room: &RoomProperties,
+        formula: ReverbFormula,
+        medium: &PropagationMedium,
+        reverb: &mut Reverb,
+    ) {
+        // Sabine's constant 24 ln(10) / c, in seconds per meter. About 0.163 for the 340 m/s of
+        // the default medium; the often quoted 0.161 is for 343 m/s.
+        let sabine_constant = 24.0 * std::f32::consts::LN_10 / medium.speed_of_sound;
+
+        for band in 0..NUM_BANDS {
+            let absorption = room.absorption[band].clamp(0.0, 0.999);
+
+            let surface_absorption = match formula {
+                ReverbFormula::Sabine => room.surface_area * absorption,
+                ReverbFormula::Eyring => -room.surface_area * (1.0 - absorption).ln(),
+            };
+
+            // The medium gives the amplitude attenuation per meter, the formulas need the energy
+            // attenuation.
+            let air_absorption = 4.0 * 2.0 * medium.absorption[band].max(0.0) * room.volume;
+
+            let total_absorption = surface_absorption + air_absorption;
+
+            reverb.reverb_times[band] = if total_absorption > f32::MIN_POSITIVE {
+                sabine_constant * room.volume / total_absorption
+            } else {
+                0.0
+            };
+        }
+    }
+
+    /// Estimates reverb times from the omnidirectional channel of an energy field. The energy
+    /// decay curve is calculated using Schroeder backward integration, and a line is fitted to
+    /// the part of the decay curve between -5 dB and -35 dB (T30). If the energy field does not
+    /// have enough dynamic range, the fit ends at -25 dB (T20) or -15 dB (T10) instead.
+    ///
+    /// Bands which contain no energy get a reverb time of 0.
+    pub fn estimate_from_energy_field(energy_field: &EnergyField, reverb: &mut Reverb) {
+        let num_bins = energy_field.num_bins();
+        let mut decay_curve = vec![0.0f32; num_bins];
+
+        for band in 0..NUM_BANDS {
+            // Schroeder backward integration.
+            let mut remaining_energy = 0.0;
+            for bin in (0..num_bins).rev() {
+                remaining_energy += energy_field.data[[0, band, bin]].max(0.0);
+                decay_curve[bin] = remaining_energy;
+            }
+
+            let total_energy = decay_curve.first().copied().unwrap_or(0.0);
+            if total_energy <= f32::MIN_POSITIVE {
+                reverb.reverb_times[band] = 0.0;
+                continue;
+            }
+
+            for value in decay_curve.iter_mut() {
+                *value = 10.0 * (*value / total_energy).log10();
+            }
+
+            reverb.reverb_times[band] = Self::fit_decay_curve(&decay_curve);
+        }
+    }
+
+    /// Fits a line to a decay curve in dB, and extrapolates it to find the time it takes for the
+    /// energy to decay by 60 dB.
+    fn fit_decay_curve(decay_curve: &[f32]) -> f32 {
+        const START_DB: f32 = -5.0;
+
+        let min_db = decay_curve
+            .iter()
+            .copied()
+            .filter(|value| value.is_finite())
+            .fold(0.0f32, f32::min);
+
+        let end_db = [-35.0, -25.0, -15.0]
+            .into_iter()
+            .find(|&end_db| min_db <= end_db)
+            .unwrap_or(min_db);
+
+        let mut points: Vec<(f32, f32)> = decay_curve
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value <= START_DB && **value >= end_db)
+            .map(|(bin, value)| (bin as f32 * BIN_DURATION, *value))
+            .collect();
+
+        // Not enough dynamic range for a proper fit, so use everything we have.
+        if points.len() < 2 {
+            points = decay_curve
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| value.is_finite())
+                .map(|(bin, value)| (bin as f32 * BIN_DURATION, *value))
+                .collect();
+        }
+
+        if points.len() < 2 {
+            return 0.0;
+        }
+
+        // Least squares fit of a line through the points.
+        let n = points.len() as f32;
+        let mean_time = points.iter().map(|(time, _)| time).sum::<f32>() / n;
+        let mean_level = points.iter().map(|(_, level)| level).sum::<f32>() / n;
+
+        let mut covariance = 0.0;
+        let mut variance = 0.0;
+        for (time, level) in &points {
+            covariance += (time - mean_time) * (level - mean_level);
+            variance += (time - mean_time) * (time - mean_time);
+        }
+
+        if variance <= f32::MIN_POSITIVE {
+            return 0.0;
+        }
+
+        let slope = covariance / variance;
+        if slope >= 0.0 {
+            return 0.0;
+        }
+
+        -60.0 / slope
+    }
+}
@@ -1,4 +1,5 @@
 use crate::dsp::audio_buffer::{AudioEffectState, AudioSettings};
+use crate::effects::spread::spread_directions;
 #[cfg(feature = "firewheel")]
 use firewheel::diff::{Diff, Patch};
 use glam::Vec3;
@@ -11,12 +12,17 @@ pub struct BinauralEffectParameters {
     /// Direction/position relative to the listener. Should not be normalized.
     /// Avoid going through 0.0, 0.0, 0.0, as this will result in a jarring change in the audio.
     pub direction: Vec3,
+    /// Apparent width of the source in degrees, from 0 for a point up to 360 for a source that
+    /// surrounds the listener. See [`spread_from_radius`](crate::effects::spread::spread_from_radius)
+    /// to derive it from the size of the source.
+    pub spread: f32,
 }
 
 impl Default for BinauralEffectParameters {
     fn default() -> Self {
         Self {
             direction: Vec3::new(0.0, 1.0, 0.0),
+            spread: 0.0,
         }
     }
 }
@@ -25,6 +31,8 @@ pub struct BinauralEffect {
     renderer: Renderer,
     sofa: Sofar,
     filter: sofar::reader::Filter,
+    /// Filter for a single direction of a spread source, before it is blended into `filter`.
+    spread_filter: sofar::reader::Filter,
     /// Direction/position relative to the listener. Should not be normalized.
     /// This may never reach Vec3::ZERO, as that will result in a panic.
     direction: Vec3,
@@ -44,6 +52,7 @@ impl BinauralEffect {
 
         let filter_len = sofa.filter_len();
         let filter = sofar::reader::Filter::new(filter_len);
+        let spread_filter = sofar::reader::Filter::new(filter_len);
 
         let renderer = Renderer::builder(filter_len)
             .with_sample_rate(sampling_rate as f32)
@@ -55,6 +64,7 @@ impl BinauralEffect {
             renderer,
             sofa,
             filter,
+            spread_filter,
             direction: Vec3::new(0.0, 1.0, 0.0),
         }
     }
@@ -72,7 +82,11 @@ impl BinauralEffect {
         }
 
         let dir = self.direction;
-        self.sofa.filter(dir.y, -dir.x, dir.z, &mut self.filter);
+        if params.spread > 0.0 {
+            self.blend_spread_filter(dir, params.spread);
+        } else {
+            self.sofa.filter(dir.y, -dir.x, dir.z, &mut self.filter);
+        }
         self.renderer.set_filter(&self.filter).unwrap();
 
         let input_data: &[f32] = input[0];
@@ -84,4 +98,48 @@ impl BinauralEffect {
 
         AudioEffectState::TailComplete
     }
+
+    /// Averages the HRTFs over a cone of `spread` degrees around `direction` into `self.filter`.
+    /// The filters are rescaled so the blend has the average energy of its parts, otherwise
+    /// the source would get quieter as it gets wider.
+    fn blend_spread_filter(&mut self, direction: Vec3, spread: f32) {
+        let directions = spread_directions(direction, spread);
+        let weight = 1.0 / directions.len() as f32;
+
+        self.filter.left.fill(0.0);
+        self.filter.right.fill(0.0);
+        self.filter.ldelay = 0.0;
+        self.filter.rdelay = 0.0;
+
+        let mut left_energy = 0.0;
+        let mut right_energy = 0.0;
+
+        for dir in directions {
+            self.sofa
+                .filter(dir.y, -dir.x, dir.z, &mut self.spread_filter);
+
+            for (blended, sample) in self.filter.left.iter_mut().zip(&self.spread_filter.left) {
+                *blended += weight * sample;
+                left_energy += weight * sample * sample;
+            }
+            for (blended, sample) in self.filter.right.iter_mut().zip(&self.spread_filter.right) {
+                *blended += weight * sample;
+                right_energy += weight * sample * sample;
+            }
+            self.filter.ldelay += weight * self.spread_filter.ldelay;
+            self.filter.rdelay += weight * self.spread_filter.rdelay;
+        }
+
+        normalize_energy(&mut self.filter.left, left_energy);
+        normalize_energy(&mut self.filter.right, right_energy);
+    }
+}
+
+/// Scales `filter` so that its energy becomes `energy`.
+fn normalize_energy(filter: &mut [f32], energy: f32) {
+    let current: f32 = filter.iter().map(|sample| sample * sample).sum();
+    if current > f32::EPSILON {
+        let scale = (energy / current).sqrt();
+        filter.iter_mut().for_each(|sample| *sample *= scale);
+    }
 }
@@ -19,6 +19,7 @@ use crate::dsp::audio_buffer::{
     AudioBuffer, AudioBufferMut, AudioEffectState, AudioSettings, ScratchBuffer,
 };
 use crate::dsp::bands::NUM_BANDS;
+use crate::dsp::fractional_delay::FractionalDelay;
 #[cfg(feature = "firewheel")]
 use firewheel::diff::{Diff, Patch};
 use std::cmp::PartialEq;
@@ -37,6 +38,7 @@ pub struct DirectApplyFlags {
     pub occlusion: bool,
     pub transmission: bool,
     pub delay: bool,
+    pub diffraction: bool,
 }
 
 impl DirectApplyFlags {
@@ -48,6 +50,7 @@ impl DirectApplyFlags {
             occlusion: true,
             transmission: true,
             delay: true,
+            diffraction: true,
         }
     }
 
@@ -59,6 +62,7 @@ impl DirectApplyFlags {
             occlusion: false,
             transmission: false,
             delay: false,
+            diffraction: false,
         }
     }
 }
@@ -70,12 +74,59 @@ pub enum TransmissionType {
     FrequencyDependent,
 }
 
+/// Attack and release times of a smoothed value, in seconds. The attack time is used while the
+/// value increases (the source becomes louder), the release time while it decreases. After
+/// either time the value has covered about 63% of the distance to its target. A time of zero
+/// disables smoothing in that direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "firewheel", derive(Diff, Patch))]
+pub struct SmoothingTimes {
+    pub attack: f32,
+    pub release: f32,
+}
+
+impl SmoothingTimes {
+    pub fn new(attack: f32, release: f32) -> Self {
+        Self { attack, release }
+    }
+
+    /// Moves `value` towards `target` over a time step of `duration` seconds.
+    fn smooth(&self, value: f32, target: f32, duration: f32) -> f32 {
+        let time = if target > value {
+            self.attack
+        } else {
+            self.release
+        };
+
+        if time <= 0.0 {
+            return target;
+        }
+
+        target + (value - target) * (-duration / time).exp()
+    }
+}
+
+/// Smoothing applied to the simulation outputs that can change abruptly, such as raycast
+/// occlusion flipping between 0 and 1 when a source passes behind a thin pole. By default
+/// nothing is smoothed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "firewheel", derive(Diff, Patch))]
+pub struct DirectSmoothing {
+    pub occlusion: SmoothingTimes,
+    pub transmission: SmoothingTimes,
+    pub directivity: SmoothingTimes,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "firewheel", derive(Diff, Patch))]
 pub struct DirectEffectParameters {
     pub direct_sound_path: DirectSoundPath,
     pub flags: DirectApplyFlags,
     pub transmission_type: TransmissionType,
+    pub smoothing: DirectSmoothing,
+    /// Scales the propagation delay, and with it the Doppler shift of moving sources. 1 is
+    /// physically correct, 0 removes both. Only used if `flags.delay` is set.
+    pub doppler_scale: f32,
 }
 
 impl Default for DirectEffectParameters {
@@ -84,26 +135,51 @@ impl Default for DirectEffectParameters {
             direct_sound_path: DirectSoundPath::default(),
             flags: DirectApplyFlags::all(),
             transmission_type: TransmissionType::FrequencyDependent,
+            smoothing: DirectSmoothing::default(),
+            doppler_scale: 1.0,
         }
     }
 }
 
+/// Longest propagation delay `DirectEffect::new` supports, in seconds. Enough for sources about
+/// 340 meters away.
+pub const DEFAULT_MAX_DELAY: f32 = 1.0;
+
 // Port note: Compared to the original code this DirectEffect applies to 1 channel only.
 /// Audio effect that applies direct sound path parameters to an incoming multichannel audio buffer.
 pub struct DirectEffect {
     pub frame_size: usize,
+    sampling_rate: u32,
     /// One filter object per channel to apply effect.
     eq_effect: EqEffect,
     /// Attenuation interpolation.
     gain_effect: GainEffect,
+    /// Smoothed version of the direct sound path, or `None` before the first frame.
+    smoothed_path: Option<DirectSoundPath>,
+    /// Propagation delay. Changes in delay between frames produce the Doppler shift.
+    delay_line: FractionalDelay,
+    /// Output of the delay line, one frame long.
+    delayed: Vec<f32>,
 }
 
 impl DirectEffect {
     pub fn new(audio_settings: AudioSettings) -> Self {
+        Self::with_max_delay(audio_settings, DEFAULT_MAX_DELAY)
+    }
+
+    /// Creates a direct effect that can delay the sound by up to `max_delay` seconds. Longer
+    /// propagation delays are clamped to this.
+    pub fn with_max_delay(audio_settings: AudioSettings, max_delay: f32) -> Self {
+        let max_delay_samples = (max_delay * audio_settings.sampling_rate as f32).ceil() as usize;
+
         Self {
             frame_size: audio_settings.frame_size,
+            sampling_rate: audio_settings.sampling_rate,
             eq_effect: EqEffect::new(audio_settings),
             gain_effect: GainEffect::new(audio_settings),
+            smoothed_path: None,
+            delay_line: FractionalDelay::new(max_delay_samples),
+            delayed: vec![0.0; audio_settings.frame_size],
         }
     }
 
@@ -111,6 +187,47 @@ impl DirectEffect {
     pub(crate) fn reset(&mut self) {
         self.eq_effect.reset();
         self.gain_effect.reset();
+        self.smoothed_path = None;
+        self.delay_line.reset();
+    }
+
+    /// Moves the smoothed occlusion, transmission and directivity towards the values in
+    /// `direct_path`, and returns `direct_path` with those values replaced by the smoothed ones.
+    fn smooth(
+        &mut self,
+        direct_path: DirectSoundPath,
+        smoothing: &DirectSmoothing,
+        num_samples: usize,
+    ) -> DirectSoundPath {
+        let Some(previous) = self.smoothed_path else {
+            self.smoothed_path = Some(direct_path);
+            return direct_path;
+        };
+
+        let duration = num_samples as f32 / self.sampling_rate as f32;
+        let mut smoothed = direct_path;
+
+        smoothed.occlusion =
+            smoothing
+                .occlusion
+                .smooth(previous.occlusion, direct_path.occlusion, duration);
+
+        for i in 0..NUM_BANDS {
+            smoothed.directivity[i] = smoothing.directivity.smooth(
+                previous.directivity[i],
+                direct_path.directivity[i],
+                duration,
+            );
+
+            smoothed.transmission[i] = smoothing.transmission.smooth(
+                previous.transmission[i],
+                direct_path.transmission[i],
+                duration,
+            );
+        }
+
+        self.smoothed_path = Some(smoothed);
+        smoothed
     }
 
     pub fn apply(
@@ -126,9 +243,38 @@ impl DirectEffect {
         // todo perf: This does not exist in the original code.
         let mut buf = ScratchBuffer::new(1, input.num_samples());
 
+        let direct_path = self.smooth(
+            parameters.direct_sound_path,
+            &parameters.smoothing,
+            input.num_samples(),
+        );
+
+        // The delay is applied first, so that the source is heard as it was when the sound left
+        // it. Without the delay the delay line is cleared, to not replay stale audio when the
+        // delay is turned on again.
+        let delayed_input: [&[f32]; 1];
+        let input = if parameters.flags.delay {
+            let delay = direct_path.delay * parameters.doppler_scale * self.sampling_rate as f32;
+            let num_samples = input.num_samples();
+            // Only grows if the input is longer than the frame size.
+            if self.delayed.len() < num_samples {
+                self.delayed.resize(num_samples, 0.0);
+            }
+
+            let delayed = &mut self.delayed[..num_samples];
+            self.delay_line.apply(delay, input[0], delayed);
+            delayed_input = [delayed];
+            &delayed_input
+        } else {
+            if self.delay_line.delay().is_some() {
+                self.delay_line.reset();
+            }
+            input
+        };
+
         // todo: This function should just take a DirectEffectParameters?
         Self::calculate_gain_and_eq(
-            parameters.direct_sound_path,
+            direct_path,
             parameters.flags,
             parameters.transmission_type,
             &mut gain,
@@ -139,7 +285,11 @@ impl DirectEffect {
         let transmission = parameters.flags.transmission;
         let transmission_freq_dep =
             parameters.transmission_type == TransmissionType::FrequencyDependent;
-        let apply_eq = air_absorption || (transmission && transmission_freq_dep);
+        let diffraction = parameters.flags.occlusion && parameters.flags.diffraction;
+        let apply_eq = air_absorption
+            || parameters.flags.directivity
+            || (transmission && transmission_freq_dep)
+            || diffraction;
 
         let gain_parameters = GainEffectParameters { gain };
 
@@ -165,6 +315,26 @@ impl DirectEffect {
         AudioEffectState::TailComplete
     }
 
+    /// Returns the total gain of `direct_path` per band, as the effect would apply it. Useful
+    /// to find out how loud a source is without rendering any audio.
+    pub fn band_gains(
+        direct_path: DirectSoundPath,
+        flags: DirectApplyFlags,
+        transmission_type: TransmissionType,
+    ) -> [f32; NUM_BANDS] {
+        let mut overall_gain = 1.0;
+        let mut eq_coefficients = [1.0; NUM_BANDS];
+        Self::calculate_gain_and_eq(
+            direct_path,
+            flags,
+            transmission_type,
+            &mut overall_gain,
+            &mut eq_coefficients,
+        );
+
+        eq_coefficients.map(|coefficient| overall_gain * coefficient)
+    }
+
     fn calculate_gain_and_eq(
         direct_path: DirectSoundPath,
         flags: DirectApplyFlags,
@@ -187,14 +357,19 @@ impl DirectEffect {
             }
         }
 
-        // Apply directivity.
+        // Apply directivity. Sources are usually more directional at high frequencies, so it
+        // is part of the EQ.
         if flags.directivity {
-            *overall_gain *= direct_path.directivity;
+            for i in 0..NUM_BANDS {
+                eq_coefficients[i] *= direct_path.directivity[i];
+            }
         }
 
         // todo double check this
         if flags.air_absorption
+            || flags.directivity
             || (flags.transmission && transmission_type == TransmissionType::FrequencyDependent)
+            || (flags.occlusion && flags.diffraction)
         {
             // Maximum value in EQ filter should be normalized to 1 and common factor rolled into attenuation factor,
             // this will allow for smooth changes to frequency changes (possible exception is if maximum remains
@@ -208,6 +383,26 @@ impl DirectEffect {
             return;
         }
 
+        // Sound that is blocked can still reach the listener through the occluder
+        // (transmission) and around its edges (diffraction).
+        if flags.diffraction {
+            let transmission = match (flags.transmission, transmission_type) {
+                (false, _) => [0.0; NUM_BANDS],
+                (true, TransmissionType::FrequencyIndependent) => {
+                    [direct_path.transmission.iter().sum::<f32>() / NUM_BANDS as f32; NUM_BANDS]
+                }
+                (true, TransmissionType::FrequencyDependent) => direct_path.transmission,
+            };
+
+            for i in 0..NUM_BANDS {
+                let blocked = (transmission[i] + direct_path.diffraction[i]).min(1.0);
+                eq_coefficients[i] *=
+                    direct_path.occlusion + (1.0 - direct_path.occlusion) * blocked;
+            }
+
+            return;
+        }
+
         // Apply occlusion and transmission.
         if flags.transmission {
             match transmission_type {
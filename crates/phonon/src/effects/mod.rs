@@ -7,3 +7,4 @@ pub mod eq;
 pub mod gain;
 pub mod panning;
 pub mod reverb;
+pub mod spread;
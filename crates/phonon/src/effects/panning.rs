@@ -17,6 +17,7 @@
 
 use crate::dsp::audio_buffer::{AudioBuffer, AudioBufferMut, AudioEffectState};
 use crate::dsp::speaker_layout::{SpeakerLayout, SpeakerLayoutType};
+use crate::effects::spread::spread_directions;
 use glam::Vec3;
 use std::f32::consts::PI;
 
@@ -36,6 +37,9 @@ struct PanningData {
 pub struct PanningEffectParameters {
     /// Direction relative to the listener. Will be normalized by the PanningEffect.
     pub direction: Vec3,
+    /// Apparent width of the source in degrees, from 0 for a point up to 360 for a source that
+    /// surrounds the listener. A wider source is panned over more of the speakers.
+    pub spread: f32,
 }
 
 /// Audio effect that applies multichannel panning coefficients to an incoming mono audio buffer.
@@ -43,6 +47,7 @@ pub struct PanningEffectParameters {
 pub struct PanningEffect {
     speaker_layout: SpeakerLayout,
     prev_direction: Vec3,
+    prev_spread: f32,
 }
 
 impl PanningEffect {
@@ -50,12 +55,14 @@ impl PanningEffect {
         Self {
             speaker_layout: SpeakerLayout::new(layout),
             prev_direction: Vec3::ZERO,
+            prev_spread: 0.0,
         }
     }
 
     #[expect(dead_code)]
     pub(crate) fn reset(&mut self) {
         self.prev_direction = Vec3::ZERO;
+        self.prev_spread = 0.0;
     }
 
     // todo: Support all channel layouts
@@ -69,10 +76,16 @@ impl PanningEffect {
         let prev_panning_data = PanningData::default();
 
         for i in 0..output.num_channels() {
-            let weight =
-                Self::panning_weight(parameters.direction, &self.speaker_layout, i, &panning_data);
-            let weight_prev = Self::panning_weight(
+            let weight = Self::spread_panning_weight(
+                parameters.direction,
+                parameters.spread,
+                &self.speaker_layout,
+                i,
+                &panning_data,
+            );
+            let weight_prev = Self::spread_panning_weight(
                 self.prev_direction,
+                self.prev_spread,
                 &self.speaker_layout,
                 i,
                 &prev_panning_data,
@@ -89,10 +102,34 @@ impl PanningEffect {
         }
 
         self.prev_direction = parameters.direction;
+        self.prev_spread = parameters.spread;
 
         AudioEffectState::TailComplete
     }
 
+    /// Returns the weight for speaker `index` for a source with the given `spread`. The source
+    /// is panned from several directions across its spread, and their weights are combined so
+    /// that the total power stays the same.
+    fn spread_panning_weight(
+        direction: Vec3,
+        spread: f32,
+        speaker_layout: &SpeakerLayout,
+        index: usize,
+        panning_data: &PanningData,
+    ) -> f32 {
+        if spread <= 0.0 {
+            return Self::panning_weight(direction, speaker_layout, index, panning_data);
+        }
+
+        let directions = spread_directions(direction, spread);
+        let power: f32 = directions
+            .iter()
+            .map(|dir| Self::panning_weight(*dir, speaker_layout, index, panning_data).powi(2))
+            .sum();
+
+        (power / directions.len() as f32).sqrt()
+    }
+
     /// Returns the weight for speaker `index` within the given `speaker_layout`.
     fn panning_weight(
         direction: Vec3,
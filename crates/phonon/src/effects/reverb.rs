@@ -21,6 +21,7 @@ use crate::dsp::delay::Delay;
 use crate::dsp::reverb_estimator::Reverb;
 
 use crate::dsp::iir::{IIR, IIRFilterer};
+use crate::random::rng_from_seed;
 use derive_deref::{Deref, DerefMut};
 use ndarray::{Array, Array2, ArrayView, Axis, s};
 use rand::Rng;
@@ -53,8 +54,14 @@ pub struct ReverbEffect {
 
 #[expect(dead_code, reason = "ReverbEffect is a WIP")]
 impl ReverbEffect {
+    /// Creates a reverb effect whose randomized delay line lengths are derived from the seed in
+    /// `audio_settings`. Effects created with the same seed sound identical.
     pub fn new(audio_settings: AudioSettings) -> Self {
-        let delay_values = Self::calc_delays_for_reverb_time(2.0, audio_settings.sampling_rate);
+        let delay_values = Self::calc_delays_for_reverb_time(
+            2.0,
+            audio_settings.sampling_rate,
+            audio_settings.seed,
+        );
 
         let delay_lines: [Delay; NUM_DELAYS] = core::array::from_fn::<_, NUM_DELAYS, _>(|i| {
             Delay::new(ALLPASS_DELAYS[i % 4], audio_settings.frame_size)
@@ -345,7 +352,11 @@ impl ReverbEffect {
         }
     }
 
-    fn calc_delays_for_reverb_time(reverb_time: f32, sampling_rate: u32) -> [i32; NUM_DELAYS] {
+    fn calc_delays_for_reverb_time(
+        reverb_time: f32,
+        sampling_rate: u32,
+        seed: u64,
+    ) -> [i32; NUM_DELAYS] {
         let mut result: [i32; NUM_DELAYS] = [0; NUM_DELAYS];
 
         const PRIMES: [i32; 16] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
@@ -353,8 +364,7 @@ impl ReverbEffect {
         let delay_sum = 0.15 * reverb_time * (sampling_rate as f32);
         let delay_min = (delay_sum / (NUM_DELAYS as f32)) as i32;
 
-        // todo: Check if rand performance and behavior is OK.
-        let mut rng = rand::rng();
+        let mut rng = rng_from_seed(seed);
 
         for i in 0..NUM_DELAYS {
             let random_offset_unsigned: u16 = rng.random();
@@ -0,0 +1,86 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use glam::Vec3;
+use std::f32::consts::TAU;
+
+/// Number of rings of directions that a spread source is rendered from.
+const NUM_RINGS: usize = 2;
+/// Number of directions on each ring.
+const NUM_DIRECTIONS_PER_RING: usize = 6;
+
+/// Returns the spread, in degrees, of a spherical source with the given `radius` at `distance`
+/// from the listener. This is the full angle the sphere takes up as seen by the listener, and
+/// 360 degrees when the listener is inside it.
+pub fn spread_from_radius(radius: f32, distance: f32) -> f32 {
+    if radius <= 0.0 {
+        return 0.0;
+    }
+
+    if distance <= radius {
+        return 360.0;
+    }
+
+    2.0 * (radius / distance).asin().to_degrees()
+}
+
+/// Returns directions that together cover a cone of `spread` degrees around `direction`, each
+/// with an equal weight. The directions have the same length as `direction`.
+///
+/// The cone is split into rings of equal solid angle, and every ring is represented by evenly
+/// spaced directions at its middle. This keeps a source with a spread of 360 degrees centered on
+/// the listener, instead of leaning towards `direction`.
+pub(crate) fn spread_directions(
+    direction: Vec3,
+    spread: f32,
+) -> [Vec3; NUM_RINGS * NUM_DIRECTIONS_PER_RING] {
+    let mut directions = [direction; NUM_RINGS * NUM_DIRECTIONS_PER_RING];
+
+    let length = direction.length();
+    if length <= f32::EPSILON {
+        return directions;
+    }
+
+    let axis = direction / length;
+    let (tangent, bitangent) = axis.any_orthonormal_pair();
+
+    // Solid angle of the cone, divided by 2π.
+    let half_angle = (spread.clamp(0.0, 360.0) / 2.0).to_radians();
+    let cone_area = 1.0 - half_angle.cos();
+
+    for (ring, ring_directions) in directions
+        .chunks_exact_mut(NUM_DIRECTIONS_PER_RING)
+        .enumerate()
+    {
+        let ring_area = cone_area * (ring as f32 + 0.5) / NUM_RINGS as f32;
+        let ring_angle = (1.0 - ring_area).clamp(-1.0, 1.0).acos();
+        let (ring_sin, ring_cos) = ring_angle.sin_cos();
+
+        // Every other ring is rotated by half a step to cover the cone more evenly.
+        let phase = 0.5 * ring as f32;
+
+        for (i, ring_direction) in ring_directions.iter_mut().enumerate() {
+            let phi = (i as f32 + phase) / NUM_DIRECTIONS_PER_RING as f32 * TAU;
+            let (phi_sin, phi_cos) = phi.sin_cos();
+            let offset = tangent * phi_cos + bitangent * phi_sin;
+
+            *ring_direction = (axis * ring_cos + offset * ring_sin) * length;
+        }
+    }
+
+    directions
+}
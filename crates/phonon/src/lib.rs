@@ -11,5 +11,7 @@ pub mod context;
 pub mod dsp;
 pub mod effects;
 pub mod models;
+pub mod probes;
+pub mod random;
 pub mod scene;
 pub mod simulators;
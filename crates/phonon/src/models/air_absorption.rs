@@ -15,7 +15,7 @@
 // limitations under the License.
 //
 
-use crate::dsp::bands::NUM_BANDS;
+use crate::dsp::bands::{CENTER_FREQUENCIES, NUM_BANDS};
 
 pub trait AirAbsorptionModel {
     fn evaluate(&self, distance: f32, band: usize) -> f32;
@@ -38,3 +38,87 @@ impl AirAbsorptionModel for DefaultAirAbsorptionModel {
         (-self.coefficients[band] * distance).exp()
     }
 }
+
+/// Air absorption computed from the weather according to ISO 9613-1, at the center frequency of
+/// each band. Dry air absorbs high frequencies much more than humid air does, so distant sounds
+/// are duller on a dry day than on a foggy one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IsoAirAbsorptionModel {
+    /// Amplitude attenuation per band, in nepers per meter.
+    coefficients: [f32; NUM_BANDS],
+}
+
+impl Default for IsoAirAbsorptionModel {
+    /// Air at 20 °C, 50% relative humidity and standard atmospheric pressure.
+    fn default() -> Self {
+        Self::new(20.0, 50.0, Self::STANDARD_PRESSURE)
+    }
+}
+
+impl IsoAirAbsorptionModel {
+    /// Standard atmospheric pressure, in kilopascal.
+    pub const STANDARD_PRESSURE: f32 = 101.325;
+
+    /// `temperature` is in degrees Celsius, `relative_humidity` in percent (0 to 100) and
+    /// `pressure` in kilopascal.
+    pub fn new(temperature: f32, relative_humidity: f32, pressure: f32) -> Self {
+        Self {
+            coefficients: CENTER_FREQUENCIES.map(|frequency| {
+                let decibels_per_meter =
+                    Self::attenuation(frequency, temperature, relative_humidity, pressure);
+                decibels_per_meter * std::f32::consts::LN_10 / 20.0
+            }),
+        }
+    }
+
+    /// Amplitude attenuation per band, in nepers per meter.
+    pub fn coefficients(&self) -> [f32; NUM_BANDS] {
+        self.coefficients
+    }
+
+    /// Attenuation of sound at `frequency` (in Hz) by the atmosphere, in dB per meter, as
+    /// given by equations 3 to 5 of ISO 9613-1. Units of the other arguments are as in `new`.
+    pub fn attenuation(
+        frequency: f32,
+        temperature: f32,
+        relative_humidity: f32,
+        pressure: f32,
+    ) -> f32 {
+        // Reference temperatures (20 °C and the triple point of water) in kelvin.
+        const REFERENCE_TEMPERATURE: f64 = 293.15;
+        const TRIPLE_POINT_TEMPERATURE: f64 = 273.16;
+
+        // Computed in double precision, the terms span many orders of magnitude.
+        let f = frequency as f64;
+        let t = temperature as f64 + 273.15;
+        let pressure_ratio = pressure as f64 / Self::STANDARD_PRESSURE as f64;
+        let t_ratio = t / REFERENCE_TEMPERATURE;
+
+        // Molar concentration of water vapour, in percent.
+        let c = -6.8346 * (TRIPLE_POINT_TEMPERATURE / t).powf(1.261) + 4.6151;
+        let saturation_pressure_ratio = 10f64.powf(c);
+        let h = relative_humidity as f64 * saturation_pressure_ratio / pressure_ratio;
+
+        // Relaxation frequencies of oxygen and nitrogen.
+        let oxygen = pressure_ratio * (24.0 + 4.04e4 * h * (0.02 + h) / (0.391 + h));
+        let nitrogen = pressure_ratio
+            * t_ratio.powf(-0.5)
+            * (9.0 + 280.0 * h * (-4.170 * (t_ratio.powf(-1.0 / 3.0) - 1.0)).exp());
+
+        let alpha = 8.686
+            * f
+            * f
+            * (1.84e-11 / pressure_ratio * t_ratio.sqrt()
+                + t_ratio.powf(-2.5)
+                    * (0.01275 * (-2239.1 / t).exp() / (oxygen + f * f / oxygen)
+                        + 0.1068 * (-3352.0 / t).exp() / (nitrogen + f * f / nitrogen)));
+
+        alpha as f32
+    }
+}
+
+impl AirAbsorptionModel for IsoAirAbsorptionModel {
+    fn evaluate(&self, distance: f32, band: usize) -> f32 {
+        (-self.coefficients[band] * distance).exp()
+    }
+}
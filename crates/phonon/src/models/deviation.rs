@@ -0,0 +1,50 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::dsp::bands::NUM_BANDS;
+
+/// Models the frequency-dependent attenuation of sound as it bends around a corner along a path
+/// from the source to the listener. `angle` is the deviation from a straight line, in radians,
+/// between 0 (no bending) and PI (sound is going back the way it came).
+pub trait DeviationModel {
+    fn evaluate(&self, angle: f32, band: usize) -> f32;
+}
+
+/// Higher frequencies have shorter wavelengths and bend around obstacles less easily, so they are
+/// attenuated more strongly for the same deviation angle.
+pub struct DefaultDeviationModel {
+    /// Per-band exponent applied to the (1 + cos(angle)) / 2 falloff.
+    pub exponents: [f32; NUM_BANDS],
+    /// Lower limit for the gain, so that sound does not disappear entirely at sharp corners.
+    pub min_gain: f32,
+}
+
+impl Default for DefaultDeviationModel {
+    fn default() -> Self {
+        Self {
+            exponents: [0.5, 1.0, 2.0],
+            min_gain: 0.01,
+        }
+    }
+}
+
+impl DeviationModel for DefaultDeviationModel {
+    fn evaluate(&self, angle: f32, band: usize) -> f32 {
+        let falloff = 0.5 * (1.0 + angle.cos());
+        falloff.powf(self.exponents[band]).max(self.min_gain)
+    }
+}
@@ -0,0 +1,58 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::dsp::bands::CENTER_FREQUENCIES;
+use crate::models::propagation_medium::PropagationMedium;
+
+/// Models the frequency-dependent attenuation of sound that bends over an edge to reach a
+/// listener in its shadow. `path_difference` is how much longer the path over the edge is than
+/// the straight line from the source to the listener, in meters.
+pub trait DiffractionModel {
+    fn evaluate(&self, path_difference: f32, band: usize) -> f32;
+}
+
+/// Maekawa's empirical barrier attenuation, `10 log10(3 + 20 N)` dB, where `N` is the Fresnel
+/// number `2 * path_difference / wavelength`.
+pub struct DefaultDiffractionModel {
+    /// Upper limit for the attenuation, in dB. Thin barriers rarely attenuate more than 20-25 dB
+    /// in practice.
+    pub max_attenuation_db: f32,
+    /// Determines the wavelength of each band.
+    pub medium: PropagationMedium,
+}
+
+impl Default for DefaultDiffractionModel {
+    fn default() -> Self {
+        Self {
+            max_attenuation_db: 20.0,
+            medium: PropagationMedium::default(),
+        }
+    }
+}
+
+impl DiffractionModel for DefaultDiffractionModel {
+    fn evaluate(&self, path_difference: f32, band: usize) -> f32 {
+        let wavelength = self.medium.wavelength(CENTER_FREQUENCIES[band]);
+        let fresnel_number = 2.0 * path_difference.max(0.0) / wavelength;
+
+        let attenuation_db = (10.0 * (3.0 + 20.0 * fresnel_number).log10())
+            .min(self.max_attenuation_db)
+            .max(0.0);
+
+        10.0f32.powf(-attenuation_db / 20.0)
+    }
+}
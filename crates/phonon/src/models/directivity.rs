@@ -15,8 +15,38 @@
 // limitations under the License.
 //
 
+use crate::dsp::bands::NUM_BANDS;
 use crate::scene::coordinate_space::CoordinateSpace3f;
 use glam::Vec3;
+use std::f32::consts::PI;
+use std::io::{self, Read};
+
+/// Gain of a sound source depending on the direction the sound is emitted in.
+pub trait DirectivityModel {
+    /// Gain of sound emitted in `direction`, given in the local coordinate space of the source
+    /// (ahead is -z).
+    fn evaluate(&self, direction: Vec3) -> f32;
+
+    /// Gain in a single frequency band. Defaults to the same gain in every band.
+    fn evaluate_band(&self, direction: Vec3, band: usize) -> f32 {
+        let _ = band;
+        self.evaluate(direction)
+    }
+
+    /// Evaluate the directivity when the listener is at `point`.
+    fn evaluate_at(&self, point: Vec3, coordinates: &CoordinateSpace3f) -> f32 {
+        let world_space_direction = (point - coordinates.origin).normalize_or_zero();
+        self.evaluate(coordinates.direction_to_local(world_space_direction))
+    }
+
+    /// Evaluate the directivity in every band when the listener is at `point`. Normally this is
+    /// called by the simulator.
+    fn evaluate_bands_at(&self, point: Vec3, coordinates: &CoordinateSpace3f) -> [f32; NUM_BANDS] {
+        let world_space_direction = (point - coordinates.origin).normalize_or_zero();
+        let direction = coordinates.direction_to_local(world_space_direction);
+        std::array::from_fn(|band| self.evaluate_band(direction, band))
+    }
+}
 
 /// Sound sources can emit sound with different intensities in different
 /// directions. For example, a megaphone mostly projects sound towards the
@@ -25,7 +55,8 @@ use glam::Vec3;
 /// to the listener, a further attenuation is applied to it, on top of any
 /// distance attenuation or air absorption.
 ///
-/// Steam Audio’s default directivity pattern is a weighted dipole.
+/// Steam Audio’s default directivity pattern is a weighted dipole. Other patterns can be
+/// used by implementing `DirectivityModel`.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Directivity {
     /// Specifies a blend between a monopole (a source that
@@ -50,22 +81,252 @@ impl Default for Directivity {
     }
 }
 
-impl Directivity {
+impl DirectivityModel for Directivity {
     fn evaluate(&self, direction: Vec3) -> f32 {
         let cosine = -direction.z;
         let base = (1.0 - self.dipole_weight) + self.dipole_weight * cosine;
         base.abs().powf(self.dipole_power)
     }
 
-    /// Evaluate the directivity when the listener is at `point`. Normally this is
-    /// called by the simulator.
-    pub fn evaluate_at(&self, point: Vec3, coordinates: &CoordinateSpace3f) -> f32 {
+    fn evaluate_at(&self, point: Vec3, coordinates: &CoordinateSpace3f) -> f32 {
         if self.dipole_weight == 0.0 {
             return 1.0;
         }
 
         let world_space_direction = (point - coordinates.origin).normalize_or_zero();
-        let local_space_direction = coordinates.direction_to_local(world_space_direction);
-        self.evaluate(local_space_direction)
+        self.evaluate(coordinates.direction_to_local(world_space_direction))
+    }
+
+    fn evaluate_bands_at(&self, point: Vec3, coordinates: &CoordinateSpace3f) -> [f32; NUM_BANDS] {
+        [self.evaluate_at(point, coordinates); NUM_BANDS]
+    }
+}
+
+/// A weighted dipole per band. Most sources get more directional as the frequency increases,
+/// so a talker that turns away sounds duller, not just quieter.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MultibandDirectivity {
+    pub bands: [Directivity; NUM_BANDS],
+}
+
+impl MultibandDirectivity {
+    pub fn new(bands: [Directivity; NUM_BANDS]) -> Self {
+        Self { bands }
+    }
+}
+
+impl DirectivityModel for MultibandDirectivity {
+    /// Average gain over all bands.
+    fn evaluate(&self, direction: Vec3) -> f32 {
+        (0..NUM_BANDS)
+            .map(|band| self.evaluate_band(direction, band))
+            .sum::<f32>()
+            / NUM_BANDS as f32
+    }
+
+    fn evaluate_band(&self, direction: Vec3, band: usize) -> f32 {
+        self.bands[band].evaluate(direction)
+    }
+}
+
+/// Classic sound cone, as found in OpenAL and the Web Audio API. Inside the inner cone the source
+/// has full volume, outside the outer cone its volume is `outer_gain`, and in between the gain
+/// changes linearly with the angle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConeDirectivity {
+    /// Full opening angle of the inner cone, in degrees.
+    pub inner_angle: f32,
+    /// Full opening angle of the outer cone, in degrees. Should not be smaller than
+    /// `inner_angle`.
+    pub outer_angle: f32,
+    /// Gain outside the outer cone.
+    pub outer_gain: f32,
+}
+
+impl Default for ConeDirectivity {
+    fn default() -> Self {
+        Self {
+            inner_angle: 360.0,
+            outer_angle: 360.0,
+            outer_gain: 0.0,
+        }
+    }
+}
+
+impl ConeDirectivity {
+    pub fn new(inner_angle: f32, outer_angle: f32, outer_gain: f32) -> Self {
+        Self {
+            inner_angle,
+            outer_angle,
+            outer_gain,
+        }
+    }
+}
+
+impl DirectivityModel for ConeDirectivity {
+    fn evaluate(&self, direction: Vec3) -> f32 {
+        let angle = (-direction.z).clamp(-1.0, 1.0).acos().to_degrees();
+        let inner = self.inner_angle / 2.0;
+        let outer = self.outer_angle / 2.0;
+
+        if angle <= inner {
+            1.0
+        } else if angle >= outer {
+            self.outer_gain
+        } else {
+            let t = (angle - inner) / (outer - inner);
+            1.0 + t * (self.outer_gain - 1.0)
+        }
+    }
+}
+
+/// Directivity balloon: per-band gains measured on a regular grid of directions around the
+/// source, for example from a loudspeaker datasheet or a recording session. Directions between
+/// the grid points are bilinearly interpolated.
+///
+/// Azimuth is measured in the horizontal plane of the source, from ahead towards the right.
+/// The azimuths are spaced evenly over the full circle, starting at 0. Elevation goes from -90
+/// (below the source) to 90 degrees (above it), with the first and last rows at the poles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabulatedDirectivity {
+    num_azimuths: usize,
+    num_elevations: usize,
+    /// Linear gains per band, ordered by elevation, then by azimuth.
+    gains: Vec<[f32; NUM_BANDS]>,
+}
+
+impl TabulatedDirectivity {
+    /// Largest number of directions `read` accepts, so that a corrupt file cannot cause a huge
+    /// allocation. Measured balloons are sampled every few degrees, so this is plenty.
+    const MAX_POINTS: usize = 1 << 20;
+
+    /// Creates a balloon from linear gains, ordered by elevation (from below), then by azimuth.
+    ///
+    /// Panics if there are fewer than one azimuth or two elevations, or if the number of gains
+    /// does not match the grid.
+    pub fn new(num_azimuths: usize, num_elevations: usize, gains: Vec<[f32; NUM_BANDS]>) -> Self {
+        assert!(num_azimuths >= 1 && num_elevations >= 2);
+        assert_eq!(gains.len(), num_azimuths * num_elevations);
+
+        Self {
+            num_azimuths,
+            num_elevations,
+            gains,
+        }
+    }
+
+    /// Reads a balloon from a text file. The file starts with the number of azimuths and
+    /// elevations, followed by one line per grid point with the gains of each band in
+    /// decibels, in the same order as `new`. Everything after a `#` is a comment.
+    ///
+    /// ```text
+    /// # azimuths elevations
+    /// 4 3
+    /// # elevation -90
+    /// -6 -9 -12
+    /// ...
+    /// ```
+    pub fn read(reader: &mut impl Read) -> io::Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+
+        let mut values = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(str::split_whitespace);
+
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut next_size = || -> io::Result<usize> {
+            values
+                .next()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| invalid("expected the size of the direction grid"))
+        };
+        let num_azimuths = next_size()?;
+        let num_elevations = next_size()?;
+
+        if num_azimuths < 1 || num_elevations < 2 {
+            return Err(invalid(
+                "need at least one azimuth and two elevations in a directivity balloon",
+            ));
+        }
+
+        let num_points = num_azimuths
+            .checked_mul(num_elevations)
+            .filter(|&num_points| num_points <= Self::MAX_POINTS)
+            .ok_or_else(|| invalid("the direction grid of the directivity balloon is too large"))?;
+
+        // Grows as gains are read, so a grid larger than the data does not reserve memory.
+        let mut gains = Vec::new();
+        for _ in 0..num_points {
+            let mut point = [0.0; NUM_BANDS];
+            for gain in point.iter_mut() {
+                let decibels: f32 = values
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| invalid("expected a gain in decibels"))?;
+                *gain = 10.0f32.powf(decibels / 20.0);
+            }
+            gains.push(point);
+        }
+
+        if values.next().is_some() {
+            return Err(invalid("unexpected data after the directivity balloon"));
+        }
+
+        Ok(Self::new(num_azimuths, num_elevations, gains))
+    }
+
+    /// Gain in a single frequency band for sound emitted in `direction`, in the local
+    /// coordinate space of the source.
+    fn gain(&self, direction: Vec3, band: usize) -> f32 {
+        let direction = direction.normalize_or_zero();
+
+        let azimuth = direction.x.atan2(-direction.z).rem_euclid(2.0 * PI);
+        let elevation = direction.y.clamp(-1.0, 1.0).asin();
+
+        let azimuth_step = 2.0 * PI / self.num_azimuths as f32;
+        let elevation_step = PI / (self.num_elevations - 1) as f32;
+
+        let a = azimuth / azimuth_step;
+        let e = ((elevation + PI / 2.0) / elevation_step).min((self.num_elevations - 1) as f32);
+
+        let a0 = (a.floor() as usize).min(self.num_azimuths - 1);
+        let a1 = (a0 + 1) % self.num_azimuths;
+        let e0 = (e.floor() as usize).min(self.num_elevations - 2);
+        let e1 = e0 + 1;
+
+        let ta = (a - a0 as f32).clamp(0.0, 1.0);
+        let te = (e - e0 as f32).clamp(0.0, 1.0);
+
+        let gain = |azimuth: usize, elevation: usize| {
+            self.gains[elevation * self.num_azimuths + azimuth][band]
+        };
+
+        let below = gain(a0, e0) + ta * (gain(a1, e0) - gain(a0, e0));
+        let above = gain(a0, e1) + ta * (gain(a1, e1) - gain(a0, e1));
+        below + te * (above - below)
+    }
+}
+
+impl DirectivityModel for TabulatedDirectivity {
+    /// Average gain over all bands.
+    fn evaluate(&self, direction: Vec3) -> f32 {
+        (0..NUM_BANDS)
+            .map(|band| self.gain(direction, band))
+            .sum::<f32>()
+            / NUM_BANDS as f32
+    }
+
+    fn evaluate_band(&self, direction: Vec3, band: usize) -> f32 {
+        self.gain(direction, band)
+    }
+}
+
+/// Any function of the direction can be used as a directivity pattern.
+impl<F: Fn(Vec3) -> f32> DirectivityModel for F {
+    fn evaluate(&self, direction: Vec3) -> f32 {
+        self(direction)
     }
 }
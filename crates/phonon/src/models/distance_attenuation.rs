@@ -19,6 +19,9 @@ pub trait DistanceAttenuationModel {
     fn evaluate(&self, distance: f32) -> f32;
 }
 
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct DefaultDistanceAttenuationModel {
     pub min_distance: f32,
 }
@@ -34,3 +37,243 @@ impl DistanceAttenuationModel for DefaultDistanceAttenuationModel {
         1.0 / self.min_distance.max(distance)
     }
 }
+
+/// Inverse distance falloff with a rolloff factor, like the clamped inverse distance model of
+/// OpenAL. The gain is 1 up to `min_distance` and stops decreasing after `max_distance`. A
+/// rolloff of 1 halves the gain with every doubling of the distance, higher values make the
+/// sound fade faster.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InverseDistanceModel {
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub rolloff: f32,
+}
+
+impl Default for InverseDistanceModel {
+    fn default() -> Self {
+        Self {
+            min_distance: 1.0,
+            max_distance: f32::INFINITY,
+            rolloff: 1.0,
+        }
+    }
+}
+
+impl DistanceAttenuationModel for InverseDistanceModel {
+    fn evaluate(&self, distance: f32) -> f32 {
+        let distance = distance.clamp(self.min_distance, self.max_distance.max(self.min_distance));
+        self.min_distance
+            / (self.min_distance + self.rolloff * (distance - self.min_distance)).max(f32::EPSILON)
+    }
+}
+
+/// Gain that falls linearly from 1 at `min_distance` to 0 at `max_distance`. Unlike the
+/// inverse models the sound is silent beyond a known distance, which makes it easy to cull.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearDistanceModel {
+    pub min_distance: f32,
+    pub max_distance: f32,
+}
+
+impl Default for LinearDistanceModel {
+    fn default() -> Self {
+        Self {
+            min_distance: 1.0,
+            max_distance: 100.0,
+        }
+    }
+}
+
+impl DistanceAttenuationModel for LinearDistanceModel {
+    fn evaluate(&self, distance: f32) -> f32 {
+        if distance <= self.min_distance {
+            return 1.0;
+        }
+
+        if distance >= self.max_distance {
+            return 0.0;
+        }
+
+        1.0 - (distance - self.min_distance) / (self.max_distance - self.min_distance)
+    }
+}
+
+/// Gain of `(distance / min_distance)^-rolloff`, like the exponent distance model of OpenAL.
+/// A rolloff of 1 is the physical inverse distance law, 2 falls off as fast as the energy.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialDistanceModel {
+    pub min_distance: f32,
+    pub rolloff: f32,
+}
+
+impl Default for ExponentialDistanceModel {
+    fn default() -> Self {
+        Self {
+            min_distance: 1.0,
+            rolloff: 1.0,
+        }
+    }
+}
+
+impl DistanceAttenuationModel for ExponentialDistanceModel {
+    fn evaluate(&self, distance: f32) -> f32 {
+        // A min_distance of 0 would divide by zero.
+        let min_distance = self.min_distance.max(f32::EPSILON);
+        (min_distance.max(distance) / min_distance).powf(-self.rolloff)
+    }
+}
+
+/// A point of a `CurveDistanceModel`.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurvePoint {
+    pub distance: f32,
+    pub gain: f32,
+}
+
+impl CurvePoint {
+    pub fn new(distance: f32, gain: f32) -> Self {
+        Self { distance, gain }
+    }
+}
+
+/// How a `CurveDistanceModel` interpolates between its points.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CurveInterpolation {
+    /// Straight lines between the points.
+    #[default]
+    Linear,
+    /// A smooth curve through the points. The curve is monotone between points, so it never
+    /// overshoots: a falloff that only decreases stays decreasing.
+    Spline,
+}
+
+/// Falloff curve authored by a sound designer. Before the first point the gain of the first
+/// point is used, after the last point the gain of the last point.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CurveDistanceModel {
+    /// Points of the curve, sorted by distance.
+    #[cfg_attr(
+        feature = "serde-serialize",
+        serde(deserialize_with = "deserialize_sorted_points")
+    )]
+    points: Vec<CurvePoint>,
+    pub interpolation: CurveInterpolation,
+}
+
+impl CurveDistanceModel {
+    /// Creates a curve from the given points, which are sorted by distance.
+    pub fn new(mut points: Vec<CurvePoint>, interpolation: CurveInterpolation) -> Self {
+        sort_points(&mut points);
+        Self {
+            points,
+            interpolation,
+        }
+    }
+
+    /// Points of the curve, sorted by distance.
+    pub fn points(&self) -> &[CurvePoint] {
+        &self.points
+    }
+
+    /// Slope of the monotone spline at point `index` (Fritsch-Carlson).
+    fn tangent(&self, index: usize) -> f32 {
+        let secant = |i: usize| {
+            let (a, b) = (self.points[i], self.points[i + 1]);
+            let width = b.distance - a.distance;
+            if width > 0.0 {
+                (b.gain - a.gain) / width
+            } else {
+                0.0
+            }
+        };
+
+        let last = self.points.len() - 1;
+        if index == 0 {
+            return secant(0);
+        }
+        if index == last {
+            return secant(last - 1);
+        }
+
+        let (before, after) = (secant(index - 1), secant(index));
+        if before * after <= 0.0 {
+            return 0.0;
+        }
+
+        // Weighted harmonic mean of the neighbouring slopes, which keeps the curve monotone.
+        let width_before = self.points[index].distance - self.points[index - 1].distance;
+        let width_after = self.points[index + 1].distance - self.points[index].distance;
+        let w1 = 2.0 * width_after + width_before;
+        let w2 = width_after + 2.0 * width_before;
+        (w1 + w2) / (w1 / before + w2 / after)
+    }
+}
+
+fn sort_points(points: &mut [CurvePoint]) {
+    points.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+}
+
+/// Curves in files may have been written by hand, so their points are sorted as well.
+#[cfg(feature = "serde-serialize")]
+fn deserialize_sorted_points<'de, D>(deserializer: D) -> Result<Vec<CurvePoint>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut points = <Vec<CurvePoint> as serde::Deserialize>::deserialize(deserializer)?;
+    sort_points(&mut points);
+    Ok(points)
+}
+
+impl DistanceAttenuationModel for CurveDistanceModel {
+    fn evaluate(&self, distance: f32) -> f32 {
+        let (Some(first), Some(last)) = (self.points.first(), self.points.last()) else {
+            return 1.0;
+        };
+
+        if distance <= first.distance {
+            return first.gain;
+        }
+        if distance >= last.distance {
+            return last.gain;
+        }
+
+        let index = self
+            .points
+            .partition_point(|point| point.distance <= distance)
+            - 1;
+        let (a, b) = (self.points[index], self.points[index + 1]);
+
+        let width = b.distance - a.distance;
+        let t = (distance - a.distance) / width;
+
+        match self.interpolation {
+            CurveInterpolation::Linear => a.gain + t * (b.gain - a.gain),
+            CurveInterpolation::Spline => {
+                // Cubic Hermite basis functions.
+                let t2 = t * t;
+                let t3 = t2 * t;
+                let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+                let h10 = t3 - 2.0 * t2 + t;
+                let h01 = -2.0 * t3 + 3.0 * t2;
+                let h11 = t3 - t2;
+
+                h00 * a.gain
+                    + h10 * width * self.tangent(index)
+                    + h01 * b.gain
+                    + h11 * width * self.tangent(index + 1)
+            }
+        }
+    }
+}
@@ -2,6 +2,10 @@
 //! but the user should be able to use a custom model as well.
 
 pub mod air_absorption;
+pub mod deviation;
+pub mod diffraction;
 pub mod directivity;
 pub mod distance_attenuation;
+pub mod occlusion;
 pub mod propagation_medium;
+pub mod transmission;
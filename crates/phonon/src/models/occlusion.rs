@@ -0,0 +1,35 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::scene::Scene;
+use crate::scene::coordinate_space::CoordinateSpace3f;
+
+/// Determines how much of the direct sound from a source reaches the listener past the geometry
+/// in between. Implement this to replace the ray traced occlusion of the `DirectSimulator`, for
+/// example with queries against a physics engine, or with a gameplay override for a door that
+/// is closed.
+pub trait OcclusionModel {
+    /// Returns the fraction of the sound that is not occluded, between 0 (fully occluded) and 1
+    /// (not occluded at all). Models that refine their result over time keep their own state,
+    /// as `RayTracedOcclusion` does.
+    fn evaluate(
+        &self,
+        scene: &Scene,
+        listener: &CoordinateSpace3f,
+        source: &CoordinateSpace3f,
+    ) -> f32;
+}
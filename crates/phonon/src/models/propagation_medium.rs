@@ -15,4 +15,86 @@
 // limitations under the License.
 //
 
-pub(crate) const SPEED_OF_SOUND: f32 = 340.0;
+use crate::dsp::bands::{CENTER_FREQUENCIES, NUM_BANDS};
+use crate::models::air_absorption::{AirAbsorptionModel, IsoAirAbsorptionModel};
+
+/// The medium sound travels through. Determines how long sound takes to arrive, and how much of
+/// it is absorbed along the way.
+///
+/// A medium can be used as an `AirAbsorptionModel` directly, which applies its `absorption`.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PropagationMedium {
+    /// Speed of sound, in meters per second.
+    pub speed_of_sound: f32,
+    /// Density, in kilograms per cubic meter.
+    pub density: f32,
+    /// Amplitude attenuation per band, in nepers per meter.
+    pub absorption: [f32; NUM_BANDS],
+}
+
+impl Default for PropagationMedium {
+    /// Air, as Steam Audio models it: a speed of sound of 340 m/s and the absorption of the
+    /// `DefaultAirAbsorptionModel`.
+    fn default() -> Self {
+        Self {
+            speed_of_sound: 340.0,
+            density: 1.225,
+            absorption: [0.0002, 0.0017, 0.182],
+        }
+    }
+}
+
+impl PropagationMedium {
+    /// Air at `temperature` degrees Celsius, with `relative_humidity` percent humidity, at
+    /// standard atmospheric pressure. The absorption follows ISO 9613-1.
+    pub fn air(temperature: f32, relative_humidity: f32) -> Self {
+        let kelvin = temperature + 273.15;
+
+        Self {
+            speed_of_sound: 331.3 * (kelvin / 273.15).sqrt(),
+            // Ideal gas law, with the specific gas constant of dry air.
+            density: IsoAirAbsorptionModel::STANDARD_PRESSURE * 1000.0 / (287.05 * kelvin),
+            absorption: IsoAirAbsorptionModel::new(
+                temperature,
+                relative_humidity,
+                IsoAirAbsorptionModel::STANDARD_PRESSURE,
+            )
+            .coefficients(),
+        }
+    }
+
+    /// Fresh water at 20 °C. Water barely absorbs sound at audible frequencies, so sounds carry
+    /// a lot further than in air.
+    pub fn water() -> Self {
+        Self {
+            speed_of_sound: 1482.0,
+            density: 998.0,
+            // Pure water term of Ainslie and McColm (1998), 0.00049 f^2 exp(-T / 27) dB per
+            // kilometer with f in kHz, at the surface and T = 20 °C.
+            absorption: CENTER_FREQUENCIES.map(|frequency| {
+                let kilohertz = frequency / 1000.0;
+                let decibels_per_meter =
+                    0.00049 * kilohertz * kilohertz * (-20.0f32 / 27.0).exp() / 1000.0;
+                decibels_per_meter * std::f32::consts::LN_10 / 20.0
+            }),
+        }
+    }
+
+    /// Time sound takes to travel `distance` meters, in seconds.
+    pub fn travel_time(&self, distance: f32) -> f32 {
+        distance / self.speed_of_sound
+    }
+
+    /// Wavelength of sound at `frequency` Hz, in meters.
+    pub fn wavelength(&self, frequency: f32) -> f32 {
+        self.speed_of_sound / frequency
+    }
+}
+
+impl AirAbsorptionModel for PropagationMedium {
+    fn evaluate(&self, distance: f32, band: usize) -> f32 {
+        (-self.absorption[band] * distance).exp()
+    }
+}
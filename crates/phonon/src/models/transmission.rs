@@ -0,0 +1,151 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::dsp::bands::NUM_BANDS;
+use crate::scene::Scene;
+use crate::scene::coordinate_space::CoordinateSpace3f;
+use crate::scene::ray::Ray;
+
+/// Determines how much of the occluded sound from a source passes through the geometry in
+/// between. Implement this to replace the ray traced transmission of the `DirectSimulator`, for
+/// example with designer-tuned values per wall.
+pub trait TransmissionModel {
+    /// Writes the fraction of the occluded sound that is transmitted to the listener, per band.
+    fn evaluate(
+        &self,
+        scene: &Scene,
+        listener: &CoordinateSpace3f,
+        source: &CoordinateSpace3f,
+        transmission: &mut [f32; NUM_BANDS],
+    );
+}
+
+/// Traces rays between the source and the listener, and multiplies the transmission
+/// coefficients of the materials that are hit.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastTransmissionModel {
+    /// Maximum number of surfaces to find between the source and the listener. With zero rays,
+    /// the transmission is left unchanged.
+    pub num_rays: usize,
+}
+
+impl Default for RaycastTransmissionModel {
+    fn default() -> Self {
+        Self { num_rays: 3 }
+    }
+}
+
+impl TransmissionModel for RaycastTransmissionModel {
+    fn evaluate(
+        &self,
+        scene: &Scene,
+        listener: &CoordinateSpace3f,
+        source: &CoordinateSpace3f,
+        transmission_factors: &mut [f32; NUM_BANDS],
+    ) {
+        // todo: Warn instead?
+        if self.num_rays == 0 {
+            return;
+        }
+
+        let listener_position = listener.origin;
+        let source_position = source.origin;
+
+        // If, after finding a hit point, we want to continue tracing the ray towards the
+        // source, then offset the ray origin by this distance along the ray direction, to
+        // prevent self-intersection.
+        let ray_offset = 1e-2f32;
+
+        // todo: I'm not sure I understand the following. Might be worth investigating.
+        // We will alternate between tracing a ray from the listener to the source, and from the source to the listener.
+        // The motivation is that if the listener observes the source go behind an object, then that object's material is
+        // most relevant in terms of the expected amount of transmitted sound, even if there are multiple other occluders
+        // between the source and the listener.
+        let rays = [
+            Ray::new(
+                listener_position,
+                (source_position - listener_position).normalize(),
+            ),
+            Ray::new(
+                source_position,
+                (listener_position - source_position).normalize(),
+            ),
+        ];
+
+        let mut current_ray_index = 0;
+        let mut hit_count = 0;
+        let mut min_distances: [f32; 2] = [0.0, 0.0];
+        let max_distance = (source_position - listener_position).length();
+
+        // Product of the transmission coefficients of all hit points.
+        let mut accumulated_transmission: [f32; NUM_BANDS] = [1.0, 1.0, 1.0];
+
+        for _ in 0..self.num_rays {
+            // Select the ray we want to trace for this iteration.
+            let ray = &rays[current_ray_index];
+            let min_distance = &mut min_distances[current_ray_index];
+
+            let hit = scene.closest_hit(ray, *min_distance, max_distance);
+
+            let hit = match hit {
+                Some(hit) => hit,
+                // If there's nothing more between the ray origin and the source, stop.
+                None => break,
+            };
+
+            hit_count += 1;
+
+            // Accumulate the product of the transmission coefficients of all materials
+            // encountered so far.
+            for j in 0..NUM_BANDS {
+                accumulated_transmission[j] *= hit.material.transmission[j];
+            }
+
+            // Calculate the origin of the next ray segment we'll trace, if any.
+            *min_distance = hit.distance + ray_offset;
+            if *min_distance >= max_distance {
+                break;
+            }
+
+            // If the total distance traveled by both rays is greater than the distance between
+            // the source and the listener, then the rays have crossed, so stop.
+            if (min_distances[0] + min_distances[1]) >= max_distance {
+                break;
+            }
+
+            // Switch to the other ray for the next iteration.
+            current_ray_index = 1 - current_ray_index;
+        }
+
+        if hit_count <= 1 {
+            // If we have only 1 hit, then use the transmission coefficients of that material.
+            // If we have no hits, this will automatically set the transmission coefficients to
+            // [1.0, 1.0, 1.0] (i.e., 100% transmission).
+            transmission_factors.copy_from_slice(accumulated_transmission.as_slice());
+        } else {
+            // We have more than one hit, so set the total transmission to the square root of the
+            // product of the transmission coefficients of all hit points. This assumes that hit
+            // points occur in pairs, e.g. both sides of a solid wall, in which case we avoid
+            // double-counting the transmission due to both sides of the wall.
+            for i in 0..NUM_BANDS {
+                transmission_factors[i] = accumulated_transmission[i].sqrt();
+            }
+        }
+    }
+}
@@ -0,0 +1,371 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::dsp::bands::NUM_BANDS;
+use crate::dsp::energy_field::EnergyField;
+use crate::dsp::reverb_estimator::{Reverb, ReverbEstimator};
+use crate::models::air_absorption::AirAbsorptionModel;
+use crate::models::propagation_medium::PropagationMedium;
+use crate::probes::{Probe, ProbeBatch};
+use crate::random::DEFAULT_SEED;
+use crate::scene::Scene;
+use crate::scene::coordinate_space::CoordinateSpace3f;
+use crate::simulators::reflection::ReflectionSimulator;
+use glam::Vec3;
+use ndarray::Array3;
+use std::io::{self, Read, Write};
+
+/// Settings used when baking reflection data.
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReflectionBakeSettings {
+    /// Number of rays traced from each probe.
+    pub num_rays: usize,
+    /// Maximum number of times a ray is reflected.
+    pub num_bounces: usize,
+    /// Length of the simulated energy fields, in seconds. Should be longer than the longest
+    /// expected reverb time.
+    pub duration: f32,
+    /// Radius of the sphere that collects energy around each probe, in meters.
+    pub receiver_radius: f32,
+    /// Whether the energy fields are stored alongside the reverb times. This allows
+    /// reconstructing full impulse responses at runtime, but uses a lot more memory.
+    pub store_energy_fields: bool,
+    /// Ambisonic order of the stored energy fields. Only used if `store_energy_fields` is set.
+    pub order: usize,
+    /// Seed for the ray tracer. Baking the same scene with the same seed gives identical data.
+    pub seed: u64,
+    /// Medium the probes are in.
+    pub medium: PropagationMedium,
+}
+
+impl Default for ReflectionBakeSettings {
+    fn default() -> Self {
+        Self {
+            num_rays: 4096,
+            num_bounces: 32,
+            duration: 2.0,
+            receiver_radius: 1.0,
+            store_energy_fields: false,
+            order: 0,
+            seed: DEFAULT_SEED,
+            medium: PropagationMedium::default(),
+        }
+    }
+}
+
+/// Reflection data baked at a single probe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BakedProbeData {
+    pub reverb: Reverb,
+    pub energy_field: Option<EnergyField>,
+}
+
+/// Listener-centric reverb baked at every probe of a probe batch.
+#[derive(Debug, Clone, Default)]
+pub struct BakedReflectionData {
+    probe_batch: ProbeBatch,
+    probe_data: Vec<BakedProbeData>,
+}
+
+/// Bakes reflection data into probes.
+pub struct ReflectionBaker;
+
+impl ReflectionBaker {
+    /// Traces rays from every probe in `probe_batch` and estimates the reverb at that probe.
+    /// This is slow, and meant to be done offline for static geometry.
+    pub fn bake(
+        scene: &Scene,
+        probe_batch: &ProbeBatch,
+        settings: &ReflectionBakeSettings,
+        air_absorption_model: &impl AirAbsorptionModel,
+    ) -> BakedReflectionData {
+        let mut simulator = ReflectionSimulator::new(
+            settings.num_rays,
+            settings.num_bounces,
+            settings.receiver_radius,
+        );
+        simulator.seed = settings.seed;
+        simulator.medium = settings.medium;
+
+        let order = if settings.store_energy_fields {
+            settings.order
+        } else {
+            0
+        };
+
+        let probe_data = probe_batch
+            .probes()
+            .iter()
+            .map(|probe| {
+                let listener = CoordinateSpace3f::from_origin(probe.position);
+                let mut energy_field = EnergyField::new(settings.duration, order);
+                simulator.simulate(scene, &listener, air_absorption_model, &mut energy_field);
+
+                let mut reverb = Reverb::default();
+                ReverbEstimator::estimate_from_energy_field(&energy_field, &mut reverb);
+
+                BakedProbeData {
+                    reverb,
+                    energy_field: settings.store_energy_fields.then_some(energy_field),
+                }
+            })
+            .collect();
+
+        let mut probe_batch = probe_batch.clone();
+        probe_batch.commit();
+
+        BakedReflectionData {
+            probe_batch,
+            probe_data,
+        }
+    }
+}
+
+impl BakedReflectionData {
+    /// Identifies a file containing baked reflection data.
+    const MAGIC: [u8; 4] = *b"PHRF";
+
+    /// Version of the file format written by `write`. Files with a different version are
+    /// rejected by `read`.
+    pub const VERSION: u32 = 1;
+
+    /// Largest number of probes `read` accepts, so that a corrupt file cannot cause a huge
+    /// allocation.
+    const MAX_PROBES: usize = 1 << 20;
+
+    /// Largest number of values in a single energy field that `read` accepts. Far more than an
+    /// ambisonics order of 3 with a minute of bins needs.
+    const MAX_ENERGY_FIELD_VALUES: usize = 1 << 24;
+
+    pub fn probe_batch(&self) -> &ProbeBatch {
+        &self.probe_batch
+    }
+
+    pub fn probe_data(&self) -> &[BakedProbeData] {
+        &self.probe_data
+    }
+
+    /// Interpolates the baked reverb times of the probes around `point`. If a scene is given,
+    /// probes that cannot be seen from `point` are ignored. Returns `None` if no probe
+    /// influences `point`.
+    pub fn reverb_at(&self, scene: Option<&Scene>, point: Vec3) -> Option<Reverb> {
+        let neighborhood = self.probe_batch.find_neighborhood(scene, point);
+        if neighborhood.is_empty() {
+            return None;
+        }
+
+        let mut reverb = Reverb {
+            reverb_times: [0.0; NUM_BANDS],
+        };
+
+        for (index, weight) in neighborhood.neighbors {
+            let probe_reverb = &self.probe_data[index].reverb;
+            for band in 0..NUM_BANDS {
+                reverb.reverb_times[band] += weight * probe_reverb.reverb_times[band];
+            }
+        }
+
+        Some(reverb)
+    }
+
+    /// Interpolates the baked energy fields of the probes around `point` into `energy_field`.
+    /// Probes without an energy field, or with one of a different size, are skipped. Returns
+    /// `false` if no energy field could be found, in which case `energy_field` is left at zero.
+    pub fn energy_field_at(
+        &self,
+        scene: Option<&Scene>,
+        point: Vec3,
+        energy_field: &mut EnergyField,
+    ) -> bool {
+        energy_field.reset();
+
+        let neighborhood = self.probe_batch.find_neighborhood(scene, point);
+
+        let mut total_weight = 0.0;
+        for (index, weight) in neighborhood.neighbors {
+            let Some(probe_field) = &self.probe_data[index].energy_field else {
+                continue;
+            };
+
+            if probe_field.data.dim() != energy_field.data.dim() {
+                continue;
+            }
+
+            energy_field.data.scaled_add(weight, &probe_field.data);
+            total_weight += weight;
+        }
+
+        if total_weight <= 0.0 {
+            return false;
+        }
+
+        // Renormalize in case some of the neighbors were skipped.
+        energy_field.data /= total_weight;
+        true
+    }
+
+    /// Writes the baked data in a compact binary format. All values are stored little-endian.
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&Self::MAGIC)?;
+        write_u32(writer, Self::VERSION)?;
+        write_u32(writer, self.probe_data.len() as u32)?;
+
+        for (probe, data) in self.probe_batch.probes().iter().zip(&self.probe_data) {
+            for value in probe.position.to_array() {
+                write_f32(writer, value)?;
+            }
+            write_f32(writer, probe.radius)?;
+
+            for value in data.reverb.reverb_times {
+                write_f32(writer, value)?;
+            }
+
+            match &data.energy_field {
+                None => writer.write_all(&[0])?,
+                Some(energy_field) => {
+                    writer.write_all(&[1])?;
+                    write_u32(writer, energy_field.num_channels() as u32)?;
+                    write_u32(writer, energy_field.num_bins() as u32)?;
+                    for &value in energy_field.data.iter() {
+                        write_f32(writer, value)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads data previously written by `write`. The probe batch is committed, so the data can
+    /// be looked up straight away.
+    pub fn read(reader: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != Self::MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a baked reflection data file",
+            ));
+        }
+
+        let version = read_u32(reader)?;
+        if version != Self::VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported baked reflection data version {version}"),
+            ));
+        }
+
+        let num_probes = read_u32(reader)? as usize;
+        if num_probes > Self::MAX_PROBES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("too many probes ({num_probes})"),
+            ));
+        }
+
+        // Nothing is reserved up front based on the header. Storage grows as data is read, so a
+        // truncated file fails before much memory is used.
+        let mut probe_batch = ProbeBatch::new();
+        let mut probe_data = Vec::new();
+
+        for _ in 0..num_probes {
+            let position = Vec3::new(read_f32(reader)?, read_f32(reader)?, read_f32(reader)?);
+            let radius = read_f32(reader)?;
+            probe_batch.add_probe(Probe::new(position, radius));
+
+            let mut reverb_times = [0.0; NUM_BANDS];
+            for value in reverb_times.iter_mut() {
+                *value = read_f32(reader)?;
+            }
+
+            let mut has_energy_field = [0];
+            reader.read_exact(&mut has_energy_field)?;
+
+            let energy_field = match has_energy_field[0] {
+                0 => None,
+                1 => {
+                    let num_channels = read_u32(reader)? as usize;
+                    let num_bins = read_u32(reader)? as usize;
+
+                    let num_values = num_channels
+                        .checked_mul(NUM_BANDS)
+                        .and_then(|size| size.checked_mul(num_bins))
+                        .filter(|&size| size <= Self::MAX_ENERGY_FIELD_VALUES)
+                        .ok_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!(
+                                    "energy field of {num_channels} channels and {num_bins} bins \
+                                     is too large"
+                                ),
+                            )
+                        })?;
+
+                    let mut values = Vec::new();
+                    for _ in 0..num_values {
+                        values.push(read_f32(reader)?);
+                    }
+
+                    let data = Array3::from_shape_vec((num_channels, NUM_BANDS, num_bins), values)
+                        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+                    Some(EnergyField { data })
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "invalid energy field flag",
+                    ));
+                }
+            };
+
+            probe_data.push(BakedProbeData {
+                reverb: Reverb { reverb_times },
+                energy_field,
+            });
+        }
+
+        probe_batch.commit();
+
+        Ok(Self {
+            probe_batch,
+            probe_data,
+        })
+    }
+}
+
+fn write_u32(writer: &mut impl Write, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_f32(writer: &mut impl Write, value: f32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
@@ -0,0 +1,117 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::probes::{Probe, ProbeBatch};
+use crate::scene::Scene;
+use crate::scene::ray::Ray;
+use glam::{Mat4, Vec3};
+
+/// How probes should be placed inside a volume.
+#[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProbeGenerationType {
+    /// A single probe at the center of the volume, large enough to cover the whole volume.
+    Centroid,
+    /// Probes on a horizontal grid with the given `spacing`. For each grid point a ray is cast
+    /// downwards from the top of the volume, and a probe is placed `height` meters above the
+    /// first surface that is hit.
+    UniformFloor { spacing: f32, height: f32 },
+}
+
+/// Places probes inside a volume of the scene.
+pub struct ProbeGenerator;
+
+impl ProbeGenerator {
+    /// Generates probes inside `volume` and adds them to `probe_batch`. `volume` is a transform
+    /// that maps the unit cube (from -0.5 to 0.5 along each axis) to an oriented box in the
+    /// scene. Its local +y axis is considered to be up.
+    ///
+    /// The probe batch still needs to be committed afterwards.
+    pub fn generate(
+        scene: &Scene,
+        volume: Mat4,
+        generation_type: ProbeGenerationType,
+        probe_batch: &mut ProbeBatch,
+    ) {
+        match generation_type {
+            ProbeGenerationType::Centroid => {
+                let center = volume.transform_point3(Vec3::ZERO);
+                let corner = volume.transform_point3(Vec3::splat(0.5));
+                probe_batch.add_probe(Probe::new(center, (corner - center).length()));
+            }
+            ProbeGenerationType::UniformFloor { spacing, height } => {
+                Self::generate_uniform_floor(scene, volume, spacing, height, probe_batch);
+            }
+        }
+    }
+
+    fn generate_uniform_floor(
+        scene: &Scene,
+        volume: Mat4,
+        spacing: f32,
+        height: f32,
+        probe_batch: &mut ProbeBatch,
+    ) {
+        if spacing <= 0.0 {
+            return;
+        }
+
+        let x_axis = volume.x_axis.truncate();
+        let y_axis = volume.y_axis.truncate();
+        let z_axis = volume.z_axis.truncate();
+
+        let volume_height = y_axis.length();
+        let down = -y_axis.normalize_or_zero();
+
+        let num_x = (x_axis.length() / spacing).floor() as usize + 1;
+        let num_z = (z_axis.length() / spacing).floor() as usize + 1;
+
+        for i in 0..num_x {
+            for j in 0..num_z {
+                // Center the grid inside the volume.
+                let u = Self::grid_coordinate(i, num_x, x_axis.length(), spacing);
+                let v = Self::grid_coordinate(j, num_z, z_axis.length(), spacing);
+
+                let origin = volume.transform_point3(Vec3::new(u, 0.5, v));
+                let ray = Ray::new(origin, down);
+
+                let Some(hit) = scene.closest_hit(&ray, 0.0, volume_height) else {
+                    continue;
+                };
+
+                // The probe has to fit inside the volume.
+                if hit.distance < height {
+                    continue;
+                }
+
+                let position = ray.point_at_distance(hit.distance - height);
+                probe_batch.add_probe(Probe::new(position, spacing));
+            }
+        }
+    }
+
+    /// Position of grid point `index` in the unit cube, for a grid of `count` points spaced
+    /// `spacing` apart along an axis that is `length` long.
+    fn grid_coordinate(index: usize, count: usize, length: f32, spacing: f32) -> f32 {
+        if length <= 0.0 {
+            return 0.0;
+        }
+
+        let offset = (length - (count - 1) as f32 * spacing) / 2.0;
+        (offset + index as f32 * spacing) / length - 0.5
+    }
+}
@@ -0,0 +1,167 @@
+//! Probes are points in the scene at which acoustic data can be precomputed or looked up.
+//! They are typically placed throughout the walkable areas of a level.
+
+use crate::scene::Scene;
+use glam::{IVec3, Vec3};
+use std::collections::HashMap;
+
+pub mod baking;
+pub mod generator;
+
+/// Maximum number of probes that are blended together when looking up data at a point.
+pub const MAX_NEIGHBORS: usize = 8;
+
+/// A single probe. The probe influences all points within `radius` of its position.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Probe {
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+impl Probe {
+    pub fn new(position: Vec3, radius: f32) -> Self {
+        Self { position, radius }
+    }
+
+    pub fn influences(&self, point: Vec3) -> bool {
+        (point - self.position).length_squared() <= self.radius * self.radius
+    }
+}
+
+/// The probes that influence a point, with the weights that should be used to interpolate
+/// between the data stored at each probe. The weights add up to 1.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProbeNeighborhood {
+    /// Pairs of probe index and weight, sorted from nearest to furthest probe.
+    pub neighbors: Vec<(usize, f32)>,
+}
+
+impl ProbeNeighborhood {
+    pub fn is_empty(&self) -> bool {
+        self.neighbors.is_empty()
+    }
+}
+
+/// A collection of probes, with a spatial lookup structure for finding the probes near a point.
+///
+/// Like the `Scene`, probes added through `add_probe` are only found by lookups after `commit`
+/// is called. Removing a probe shifts the indices of the probes after it, so `remove_probe`
+/// discards the lookup structure and nothing is found until the next `commit`.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct ProbeBatch {
+    probes: Vec<Probe>,
+
+    /// Uniform grid of probe indices. The cell size is the largest probe radius, so that only
+    /// the cells directly around a point need to be checked.
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    grid: HashMap<IVec3, Vec<usize>>,
+
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    cell_size: f32,
+}
+
+impl ProbeBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_probe(&mut self, probe: Probe) {
+        self.probes.push(probe);
+    }
+
+    /// Removes the probe at `index`. Note that this shifts the indices of all subsequent probes.
+    pub fn remove_probe(&mut self, index: usize) {
+        self.probes.remove(index);
+
+        // The grid refers to probes by index, so it is no longer valid.
+        self.grid.clear();
+    }
+
+    pub fn num_probes(&self) -> usize {
+        self.probes.len()
+    }
+
+    pub fn probes(&self) -> &[Probe] {
+        &self.probes
+    }
+
+    pub fn positions(&self) -> Vec<Vec3> {
+        self.probes.iter().map(|probe| probe.position).collect()
+    }
+
+    /// Rebuilds the spatial lookup structure. Must be called after adding or removing probes,
+    /// and after deserializing a probe batch.
+    pub fn commit(&mut self) {
+        self.grid.clear();
+
+        self.cell_size = self
+            .probes
+            .iter()
+            .map(|probe| probe.radius)
+            .fold(0.0, f32::max)
+            .max(f32::MIN_POSITIVE);
+
+        for (index, probe) in self.probes.iter().enumerate() {
+            let cell = self.cell(probe.position);
+            self.grid.entry(cell).or_default().push(index);
+        }
+    }
+
+    /// Finds up to `MAX_NEIGHBORS` probes that influence `point`. If a scene is given, probes
+    /// that cannot be seen from `point` are ignored, so that data does not leak through walls.
+    /// Weights are based on the inverse distance to each probe.
+    pub fn find_neighborhood(&self, scene: Option<&Scene>, point: Vec3) -> ProbeNeighborhood {
+        if self.grid.is_empty() {
+            return ProbeNeighborhood::default();
+        }
+
+        let center = self.cell(point);
+        let mut candidates: Vec<(usize, f32)> = Vec::new();
+
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    let Some(indices) = self.grid.get(&(center + IVec3::new(x, y, z))) else {
+                        continue;
+                    };
+
+                    for &index in indices {
+                        let probe = &self.probes[index];
+                        if probe.influences(point) {
+                            candidates.push((index, (probe.position - point).length()));
+                        }
+                    }
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+        if let Some(scene) = scene {
+            candidates.retain(|(index, _)| !scene.is_occluded(point, self.probes[*index].position));
+        }
+
+        candidates.truncate(MAX_NEIGHBORS);
+
+        // Avoid dividing by zero when the point is exactly at a probe.
+        const MIN_DISTANCE: f32 = 1e-3;
+
+        let mut neighbors: Vec<(usize, f32)> = candidates
+            .into_iter()
+            .map(|(index, distance)| (index, 1.0 / distance.max(MIN_DISTANCE)))
+            .collect();
+
+        let total_weight: f32 = neighbors.iter().map(|(_, weight)| weight).sum();
+        for (_, weight) in neighbors.iter_mut() {
+            *weight /= total_weight;
+        }
+
+        ProbeNeighborhood { neighbors }
+    }
+
+    fn cell(&self, point: Vec3) -> IVec3 {
+        (point / self.cell_size).floor().as_ivec3()
+    }
+}
@@ -0,0 +1,119 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use glam::{Mat4, Vec3};
+use std::collections::HashMap;
+
+/// Edges between triangles whose normals differ less than this (about 8 degrees) are considered
+/// to lie on a flat surface, and do not diffract sound.
+const FLAT_EDGE_THRESHOLD: f32 = 0.99;
+
+/// A straight edge of the scene geometry around which sound can diffract.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+impl Edge {
+    pub fn new(start: Vec3, end: Vec3) -> Self {
+        Self { start, end }
+    }
+
+    pub(crate) fn transformed(&self, transform: &Mat4) -> Self {
+        Self {
+            start: transform.transform_point3(self.start),
+            end: transform.transform_point3(self.end),
+        }
+    }
+
+    /// Returns the point on this edge that is closest to the segment from `a` to `b`.
+    ///
+    /// Based on the segment-segment test from Real-Time Collision Detection, C. Ericson, 2005.
+    pub(crate) fn closest_point_to_segment(&self, a: Vec3, b: Vec3) -> Vec3 {
+        let d1 = self.end - self.start;
+        let d2 = b - a;
+        let r = self.start - a;
+
+        let length1 = d1.length_squared();
+        let length2 = d2.length_squared();
+
+        if length1 <= f32::EPSILON {
+            return self.start;
+        }
+
+        let f = d2.dot(r);
+        let c = d1.dot(r);
+
+        let s = if length2 <= f32::EPSILON {
+            (-c / length1).clamp(0.0, 1.0)
+        } else {
+            let b = d1.dot(d2);
+            let denominator = length1 * length2 - b * b;
+
+            let mut s = if denominator > f32::EPSILON {
+                ((b * f - c * length2) / denominator).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+
+            let t = (b * s + f) / length2;
+            if t < 0.0 {
+                s = (-c / length1).clamp(0.0, 1.0);
+            } else if t > 1.0 {
+                s = ((b - c) / length1).clamp(0.0, 1.0);
+            }
+
+            s
+        };
+
+        self.start + d1 * s
+    }
+}
+
+/// Finds the edges of a triangle mesh that can diffract sound: edges that belong to a single
+/// triangle (the border of an open surface), and edges between triangles that are not coplanar.
+pub(crate) fn find_diffraction_edges(vertices: &[Vec3], triangles: &[[u32; 3]]) -> Vec<Edge> {
+    // For each undirected edge, the normals of the triangles that share it.
+    let mut shared_edges: HashMap<(u32, u32), Vec<Vec3>> = HashMap::new();
+
+    for triangle in triangles {
+        let [v0, v1, v2] = triangle.map(|index| vertices[index as usize]);
+        let normal = (v1 - v0).cross(v2 - v0).normalize_or_zero();
+
+        for (i, j) in [(0, 1), (1, 2), (2, 0)] {
+            let key = (triangle[i].min(triangle[j]), triangle[i].max(triangle[j]));
+            shared_edges.entry(key).or_default().push(normal);
+        }
+    }
+
+    let mut edges: Vec<((u32, u32), Edge)> = shared_edges
+        .into_iter()
+        .filter(|(_, normals)| match normals.as_slice() {
+            [a, b] => a.dot(*b) < FLAT_EDGE_THRESHOLD,
+            _ => true,
+        })
+        .map(|((i, j), _)| {
+            let edge = Edge::new(vertices[i as usize], vertices[j as usize]);
+            ((i, j), edge)
+        })
+        .collect();
+
+    // Keep the order deterministic.
+    edges.sort_by_key(|(key, _)| *key);
+    edges.into_iter().map(|(_, edge)| edge).collect()
+}
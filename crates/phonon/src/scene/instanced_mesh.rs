@@ -16,6 +16,7 @@
 //
 
 use crate::scene::Scene;
+use crate::scene::edge::Edge;
 use crate::scene::hit::Hit;
 use crate::scene::ray::Ray;
 use glam::Mat4;
@@ -35,6 +36,10 @@ pub struct InstancedMesh {
     inverse_transform: Mat4,
     /// Flag indicating whether this instanced mesh has changed since the last call to commit().
     has_changed: bool,
+    /// Diffraction edges of the sub-scene in world space, updated by commit().
+    diffraction_edges: Vec<Edge>,
+    /// Change version of the sub-scene the diffraction edges were gathered from.
+    edges_version: Option<u32>,
 }
 
 impl InstancedMesh {
@@ -45,25 +50,47 @@ impl InstancedMesh {
             transform,
             inverse_transform: transform.inverse(),
             has_changed: false,
+            diffraction_edges: Vec::new(),
+            edges_version: None,
         })
     }
 
     pub fn set_transform(&mut self, transform: Mat4) {
+        // Setting the same transform again should not count as a change, otherwise the scene's
+        // change version would be bumped every frame.
+        if transform == self.transform {
+            return;
+        }
+
         self.transform = transform;
         self.inverse_transform = transform.inverse();
-        //self.has_changed = true; // if something is broken uncomment this (todo remove me)
+        self.has_changed = true;
     }
 
-    pub(crate) fn commit(&mut self) {
-        self.sub_scene.lock().unwrap().commit();
+    /// Commits the sub-scene. Returns whether this instanced mesh changed since the previous
+    /// commit, either because it was moved or because its sub-scene changed.
+    pub(crate) fn commit(&mut self) -> bool {
+        let mut sub_scene = self.sub_scene.lock().unwrap();
+        sub_scene.commit();
+
+        // Only transform the edges again if the instance moved or the sub-scene itself changed.
+        let version = sub_scene.change_version();
+        let changed = self.has_changed || self.edges_version != Some(version);
+        if changed {
+            self.diffraction_edges = sub_scene
+                .diffraction_edges()
+                .iter()
+                .map(|edge| edge.transformed(&self.transform))
+                .collect();
+            self.edges_version = Some(version);
+        }
+        drop(sub_scene);
 
         // After calling commit(), this instanced mesh will be considered unchanged until a subsequent call to
-        // updateTransform() changes the transform matrix.
+        // set_transform() changes the transform matrix.
         self.has_changed = false;
-    }
 
-    pub(crate) fn has_changed(&self) -> bool {
-        self.has_changed
+        changed
     }
 
     pub(crate) fn closest_hit(
@@ -97,6 +124,12 @@ impl InstancedMesh {
             .any_hit(&transformed_ray, min_distance, max_distance)
     }
 
+    /// Diffraction edges of the sub-scene, transformed to the position of this instance as of
+    /// the last commit().
+    pub(crate) fn diffraction_edges(&self) -> &[Edge] {
+        &self.diffraction_edges
+    }
+
     /// Returns a `Ray` transformed back to the original mesh transformation.
     /// `min_distance` and `max_distance` get changed accordingly.
     fn inverse_transform_ray(
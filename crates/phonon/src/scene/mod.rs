@@ -1,5 +1,6 @@
 //! Everything related to ray tracing and representing a scene in 3D space.
 
+use crate::scene::edge::Edge;
 use crate::scene::hit::Hit;
 use crate::scene::instanced_mesh::InstancedMesh;
 use crate::scene::ray::Ray;
@@ -8,6 +9,7 @@ use glam::Vec3;
 use std::sync::{Arc, Mutex};
 
 pub mod coordinate_space;
+pub mod edge;
 pub mod hit;
 pub mod instanced_mesh;
 pub mod material;
@@ -46,6 +48,10 @@ pub struct Scene {
     /// The change version of the scene.
     #[cfg_attr(feature = "serde-serialize", serde(skip))]
     change_version: u32,
+
+    /// Edges of all committed meshes that can diffract sound. Rebuilt by `commit`.
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    diffraction_edges: Vec<Edge>,
 }
 
 impl Scene {
@@ -78,6 +84,13 @@ impl Scene {
         self.has_changed = true;
     }
 
+    /// Incremented by `commit` whenever the scene has changed since the previous commit, which
+    /// includes instanced meshes that were moved. Can be used to tell whether simulation results
+    /// obtained with an earlier version of the scene are still valid.
+    pub fn change_version(&self) -> u32 {
+        self.change_version
+    }
+
     pub fn get_num_meshes_static(&self) -> usize {
         self.static_meshes[0].len()
     }
@@ -88,14 +101,14 @@ impl Scene {
 
     // todo copy docs on commit and other functions
     pub fn commit(&mut self) {
-        // If no static/instanced meshes have been added or removed since the last commit(), check to see if any
-        // instanced meshes have had their transforms updated.
-        if !self.has_changed {
-            for instanced_mesh in &self.instanced_meshes[0] {
-                if instanced_mesh.lock().unwrap().has_changed() {
-                    self.has_changed = true;
-                    break;
-                }
+        self.static_meshes[0] = self.static_meshes[1].clone();
+        self.instanced_meshes[0] = self.instanced_meshes[1].clone();
+
+        // Besides meshes being added or removed, the scene has changed if an instanced mesh was
+        // moved or its sub-scene changed.
+        for instanced_mesh in &self.instanced_meshes[0] {
+            if instanced_mesh.lock().unwrap().commit() {
+                self.has_changed = true;
             }
         }
 
@@ -104,11 +117,20 @@ impl Scene {
             self.change_version += 1;
         }
 
-        self.static_meshes[0] = self.static_meshes[1].clone();
-        self.instanced_meshes[0] = self.instanced_meshes[1].clone();
+        // Each mesh keeps its own edges cached, so they only need to be gathered again when
+        // something changed.
+        if self.has_changed {
+            self.diffraction_edges.clear();
 
-        for instanced_mesh in &self.instanced_meshes[0] {
-            instanced_mesh.lock().unwrap().commit();
+            for static_mesh in &self.static_meshes[0] {
+                self.diffraction_edges
+                    .extend_from_slice(static_mesh.diffraction_edges());
+            }
+
+            for instanced_mesh in &self.instanced_meshes[0] {
+                self.diffraction_edges
+                    .extend_from_slice(instanced_mesh.lock().unwrap().diffraction_edges());
+            }
         }
 
         // The scene will be considered unchanged until something is changed subsequently.
@@ -177,6 +199,10 @@ impl Scene {
         false
     }
 
+    pub(crate) fn diffraction_edges(&self) -> &[Edge] {
+        &self.diffraction_edges
+    }
+
     pub(crate) fn is_occluded(&self, from: Vec3, to: Vec3) -> bool {
         let direction = (to - from).normalize_or_zero();
         let distance = (to - from).length();
@@ -249,4 +275,63 @@ mod tests {
 
         assert!(scene.any_hit(&ray_hit, 0.0, 10.0));
     }
+
+    #[test]
+    fn diffraction_edges_follow_instanced_meshes() {
+        let material = Material {
+            absorption: [0.1, 0.1, 0.1],
+            scattering: 0.05,
+            transmission: [0.0, 0.0, 0.0],
+        };
+
+        let static_mesh = Arc::new(StaticMesh::new(
+            vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ],
+            vec![Triangle { indices: [0, 1, 2] }],
+            vec![0],
+            vec![material],
+        ));
+
+        let sub_scene = Arc::new(Mutex::new(Scene::new()));
+        sub_scene
+            .lock()
+            .unwrap()
+            .add_static_mesh(static_mesh.clone());
+
+        let instanced_mesh = Arc::new(InstancedMesh::new(sub_scene.clone(), Mat4::IDENTITY));
+
+        let mut scene = Scene::new();
+        scene.add_instanced_mesh(instanced_mesh.clone());
+        scene.commit();
+
+        assert_eq!(scene.diffraction_edges().len(), 3);
+        assert_eq!(scene.diffraction_edges()[0].start, Vec3::ZERO);
+
+        // Committing an unchanged scene keeps the same version and edges.
+        let version = scene.change_version();
+        scene.commit();
+        assert_eq!(scene.change_version(), version);
+        assert_eq!(scene.diffraction_edges().len(), 3);
+
+        let offset = Vec3::new(0.0, 0.0, 5.0);
+        instanced_mesh
+            .lock()
+            .unwrap()
+            .set_transform(Mat4::from_translation(offset));
+        scene.commit();
+
+        assert!(scene.change_version() > version);
+        assert_eq!(scene.diffraction_edges()[0].start, offset);
+
+        // Changing the sub-scene changes the scene that instances it.
+        let version = scene.change_version();
+        sub_scene.lock().unwrap().remove_static_mesh(static_mesh);
+        scene.commit();
+
+        assert!(scene.change_version() > version);
+        assert!(scene.diffraction_edges().is_empty());
+    }
 }
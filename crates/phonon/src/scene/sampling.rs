@@ -37,6 +37,22 @@ pub(crate) fn generate_sphere_volume_sample(i: usize) -> Vec3 {
     Vec3::new(x, y, z)
 }
 
+/// Generate `num_samples` directions that are evenly distributed over the surface of the unit
+/// sphere, using a Fibonacci lattice.
+pub(crate) fn generate_sphere_surface_samples(num_samples: usize) -> Vec<Vec3> {
+    let golden_angle = PI * (3.0 - 5.0f32.sqrt());
+
+    (0..num_samples)
+        .map(|i| {
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / num_samples as f32;
+            let radius = (1.0 - y * y).max(0.0).sqrt();
+            let phi = golden_angle * i as f32;
+
+            Vec3::new(radius * phi.cos(), y, radius * phi.sin())
+        })
+        .collect()
+}
+
 pub(crate) fn transform_sphere_volume_sample(sample: Vec3, sphere: Sphere) -> Vec3 {
     sphere.center + (sample * sphere.radius)
 }
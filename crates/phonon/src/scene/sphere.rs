@@ -17,6 +17,7 @@
 
 use glam::Vec3;
 
+#[derive(Debug, Clone, Copy)]
 pub(crate) struct Sphere {
     pub(crate) center: Vec3,
     pub(crate) radius: f32,
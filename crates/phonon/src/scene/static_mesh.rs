@@ -15,6 +15,7 @@
 // limitations under the License.
 //
 
+use crate::scene::edge::{Edge, find_diffraction_edges};
 use crate::scene::hit::Hit;
 use crate::scene::material::Material;
 use crate::scene::mesh::Mesh;
@@ -23,6 +24,7 @@ use crate::scene::triangle::Triangle;
 use glam::Vec3;
 use ndarray::Array1;
 use parry3d::query::RayCast;
+use std::sync::OnceLock;
 
 /// A static triangle mesh. The geometry of this mesh is assumed to never change at runtime. It is described in
 /// world-space coordinates. Materials are specified for each triangle.
@@ -31,6 +33,9 @@ pub struct StaticMesh {
     mesh: Mesh,
     material_indices: Array1<usize>,
     materials: Array1<Material>,
+    /// Edges that can diffract sound. The geometry never changes, so they are only found once.
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    diffraction_edges: OnceLock<Vec<Edge>>,
 }
 
 /// An IStaticMesh implementation that uses the built-in ray tracer backend.
@@ -45,6 +50,7 @@ impl StaticMesh {
             mesh: Mesh::new(vertices, triangles),
             material_indices: material_indices.into(),
             materials: materials.into(),
+            diffraction_edges: OnceLock::new(),
         }
     }
 
@@ -69,6 +75,7 @@ impl StaticMesh {
             mesh: Mesh::new(vertices, triangles),
             material_indices: material_indices.into(),
             materials: materials.into(),
+            diffraction_edges: OnceLock::new(),
         }
     }
 
@@ -91,6 +98,7 @@ impl StaticMesh {
             mesh,
             material_indices: Array1::zeros(num_triangles),
             materials: materials.into(),
+            diffraction_edges: OnceLock::new(),
         }
     }
 
@@ -140,6 +148,20 @@ impl StaticMesh {
             .cast_local_ray(&ray.0, max_distance, false)
             .is_some()
     }
+
+    pub(crate) fn diffraction_edges(&self) -> &[Edge] {
+        self.diffraction_edges.get_or_init(|| {
+            let vertices: Vec<Vec3> = self
+                .mesh
+                .mesh
+                .vertices()
+                .iter()
+                .map(|vertex| (*vertex).into())
+                .collect();
+
+            find_diffraction_edges(&vertices, self.mesh.mesh.indices())
+        })
+    }
 }
 
 #[cfg(test)]
@@ -18,21 +18,51 @@
 use crate::dsp::bands::NUM_BANDS;
 use crate::effects::direct::DirectApplyFlags;
 use crate::models::air_absorption::AirAbsorptionModel;
-use crate::models::directivity::Directivity;
+use crate::models::diffraction::DiffractionModel;
+use crate::models::directivity::DirectivityModel;
 use crate::models::distance_attenuation::DistanceAttenuationModel;
-use crate::models::propagation_medium::SPEED_OF_SOUND;
+use crate::models::occlusion::OcclusionModel;
+use crate::models::propagation_medium::PropagationMedium;
+use crate::models::transmission::TransmissionModel;
 use crate::scene::Scene;
 use crate::scene::coordinate_space::CoordinateSpace3f;
-use crate::scene::ray::Ray;
 use crate::scene::sampling::{generate_sphere_volume_sample, transform_sphere_volume_sample};
 use crate::scene::sphere::Sphere;
 use glam::Vec3;
+use std::cell::Cell;
 
 #[cfg_attr(feature = "reflect", derive(bevy_reflect::Reflect))]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OcclusionType {
     Raycast,
+    /// Samples a sphere around the source, and around the listener if it has a radius, and
+    /// uses the fraction of samples that can see each other.
     Volumetric,
+    /// Like `Volumetric`, but every update only traces a subset of the samples, continuing
+    /// where the previous update left off. The results are accumulated over time using an
+    /// exponential moving average, where `smoothing` (between 0 and 1) is the weight of the
+    /// previous result. The progress is kept in the `OcclusionState` of the
+    /// `RayTracedOcclusion`, which should be kept per source.
+    ProgressiveVolumetric {
+        smoothing: f32,
+    },
+}
+
+/// Per-source state for occlusion models that refine their result over time, such as
+/// `OcclusionType::ProgressiveVolumetric`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OcclusionState {
+    /// Index of the first sample to trace in the next update.
+    next_sample: usize,
+    /// Accumulated occlusion, or `None` if nothing has been accumulated yet.
+    occlusion: Option<f32>,
+}
+
+impl OcclusionState {
+    /// Discards the accumulated occlusion, for example when a source teleports.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
 }
 
 /// Describes the properties of a direct sound path.
@@ -48,7 +78,11 @@ pub struct DirectSoundPath {
     pub delay: f32,
     pub occlusion: f32,
     pub transmission: [f32; NUM_BANDS],
-    pub directivity: f32,
+    /// Per-band gain due to the directivity of the source.
+    pub directivity: [f32; NUM_BANDS],
+    /// Per-band fraction of the occluded sound that reaches the listener by bending over the
+    /// edges of the occluding geometry.
+    pub diffraction: [f32; NUM_BANDS],
 }
 
 impl Default for DirectSoundPath {
@@ -59,7 +93,127 @@ impl Default for DirectSoundPath {
             delay: 0.0,
             occlusion: 1.0,
             transmission: [0.1, 0.1, 0.1],
-            directivity: 1.0,
+            directivity: [1.0, 1.0, 1.0],
+            diffraction: [0.0, 0.0, 0.0],
+        }
+    }
+}
+
+impl DirectSoundPath {
+    /// Combines the paths of a source to several listeners into one, for example to play a
+    /// split-screen game over a single set of speakers. Every value is averaged using the
+    /// weight given with its path, except for the delay: averaging arrival times has no
+    /// physical meaning, so the delay is that of the earliest path with a positive weight.
+    /// Returns the default path if the weights sum to zero.
+    pub fn mix(paths: impl IntoIterator<Item = (DirectSoundPath, f32)>) -> Self {
+        let mut mixed = DirectSoundPath {
+            distance_attenuation: 0.0,
+            air_absorption: [0.0; NUM_BANDS],
+            delay: f32::INFINITY,
+            occlusion: 0.0,
+            transmission: [0.0; NUM_BANDS],
+            directivity: [0.0; NUM_BANDS],
+            diffraction: [0.0; NUM_BANDS],
+        };
+        let mut total_weight = 0.0;
+
+        for (path, weight) in paths {
+            total_weight += weight;
+
+            mixed.distance_attenuation += weight * path.distance_attenuation;
+            if weight > 0.0 {
+                mixed.delay = mixed.delay.min(path.delay);
+            }
+            mixed.occlusion += weight * path.occlusion;
+            for band in 0..NUM_BANDS {
+                mixed.air_absorption[band] += weight * path.air_absorption[band];
+                mixed.transmission[band] += weight * path.transmission[band];
+                mixed.directivity[band] += weight * path.directivity[band];
+                mixed.diffraction[band] += weight * path.diffraction[band];
+            }
+        }
+
+        if total_weight <= 0.0 {
+            return DirectSoundPath::default();
+        }
+
+        mixed.distance_attenuation /= total_weight;
+        mixed.occlusion /= total_weight;
+        for band in 0..NUM_BANDS {
+            mixed.air_absorption[band] /= total_weight;
+            mixed.transmission[band] /= total_weight;
+            mixed.directivity[band] /= total_weight;
+            mixed.diffraction[band] /= total_weight;
+        }
+
+        mixed
+    }
+}
+
+/// The built-in occlusion of the `DirectSimulator`, which traces rays through the scene in the
+/// way selected by an `OcclusionType`. Created with `DirectSimulator::occlusion_model`.
+pub struct RayTracedOcclusion<'a> {
+    simulator: &'a DirectSimulator,
+    pub occlusion_type: OcclusionType,
+    /// Size of the source when `occlusion_type` is volumetric.
+    pub source_radius: f32,
+    /// Size of the listener when `occlusion_type` is volumetric. Zero treats the listener as a
+    /// point.
+    pub listener_radius: f32,
+    /// Number of samples used when `occlusion_type` is volumetric. Limited by
+    /// `max_occlusion_samples` of the `DirectSimulator`.
+    pub num_samples: usize,
+    /// Progress of `OcclusionType::ProgressiveVolumetric`, updated by every evaluation.
+    state: Cell<OcclusionState>,
+}
+
+impl RayTracedOcclusion<'_> {
+    /// Continues from the state of an earlier update of the same source.
+    pub fn with_state(mut self, state: OcclusionState) -> Self {
+        self.state = Cell::new(state);
+        self
+    }
+
+    /// Returns the state after the last evaluation, to be passed to `with_state` in the next
+    /// update of the same source.
+    pub fn state(&self) -> OcclusionState {
+        self.state.get()
+    }
+}
+
+impl OcclusionModel for RayTracedOcclusion<'_> {
+    fn evaluate(
+        &self,
+        scene: &Scene,
+        listener: &CoordinateSpace3f,
+        source: &CoordinateSpace3f,
+    ) -> f32 {
+        let listener_sphere = Sphere::new(listener.origin, self.listener_radius);
+        let source_sphere = Sphere::new(source.origin, self.source_radius);
+
+        match self.occlusion_type {
+            OcclusionType::Raycast => {
+                DirectSimulator::raycast_occlusion(scene, listener.origin, source.origin)
+            }
+            OcclusionType::Volumetric => self.simulator.raycast_volumetric(
+                scene,
+                listener_sphere,
+                source_sphere,
+                self.num_samples,
+            ),
+            OcclusionType::ProgressiveVolumetric { smoothing } => {
+                let mut state = self.state.get();
+                let occlusion = self.simulator.raycast_volumetric_progressive(
+                    scene,
+                    listener_sphere,
+                    source_sphere,
+                    self.num_samples,
+                    smoothing,
+                    &mut state,
+                );
+                self.state.set(state);
+                occlusion
+            }
         }
     }
 }
@@ -74,6 +228,8 @@ pub struct DirectSimulator {
     /// These sampling points are transformed to the source position
     /// when calculating the volumetric occlusion.
     sphere_volume_samples: Vec<Vec3>,
+    /// Determines the propagation delay.
+    pub medium: PropagationMedium,
 }
 
 impl DirectSimulator {
@@ -86,9 +242,30 @@ impl DirectSimulator {
 
         Self {
             sphere_volume_samples,
+            medium: PropagationMedium::default(),
         }
     }
 
+    /// Returns the built-in occlusion model, for use with `simulate`.
+    pub fn occlusion_model(
+        &self,
+        occlusion_type: OcclusionType,
+        source_radius: f32,
+        listener_radius: f32,
+        num_samples: usize,
+    ) -> RayTracedOcclusion<'_> {
+        RayTracedOcclusion {
+            simulator: self,
+            occlusion_type,
+            source_radius,
+            listener_radius,
+            num_samples,
+            state: Cell::default(),
+        }
+    }
+
+    /// Simulates the direct sound path from `source` to `listener`. Occlusion and transmission
+    /// are only evaluated if a `scene` is given.
     #[expect(clippy::too_many_arguments)]
     pub fn simulate(
         &self,
@@ -96,13 +273,12 @@ impl DirectSimulator {
         flags: DirectApplyFlags,
         source: &CoordinateSpace3f,
         listener: &CoordinateSpace3f,
-        distance_attenuation_model: &impl DistanceAttenuationModel,
-        air_absorption_model: &impl AirAbsorptionModel,
-        directivity: Directivity,
-        occlusion_type: OcclusionType,
-        occlusion_radius: f32,
-        num_occlusion_samples: usize,
-        num_transmission_rays: usize,
+        distance_attenuation_model: &(impl DistanceAttenuationModel + ?Sized),
+        air_absorption_model: &(impl AirAbsorptionModel + ?Sized),
+        diffraction_model: &(impl DiffractionModel + ?Sized),
+        directivity_model: &(impl DirectivityModel + ?Sized),
+        occlusion_model: &(impl OcclusionModel + ?Sized),
+        transmission_model: &(impl TransmissionModel + ?Sized),
         direct_sound_path: &mut DirectSoundPath,
     ) {
         let distance = (source.origin - listener.origin).length();
@@ -122,55 +298,52 @@ impl DirectSimulator {
         }
 
         if flags.delay {
-            direct_sound_path.delay = Self::direct_path_delay(listener.origin, source.origin);
+            direct_sound_path.delay = self.medium.travel_time(distance);
         } else {
             direct_sound_path.delay = 0.0;
         }
 
         if flags.directivity {
-            direct_sound_path.directivity = directivity.evaluate_at(listener.origin, source);
+            direct_sound_path.directivity =
+                directivity_model.evaluate_bands_at(listener.origin, source);
         } else {
-            direct_sound_path.directivity = 1.0;
+            direct_sound_path.directivity.fill(1.0);
         }
 
         if let Some(scene) = scene {
             if flags.occlusion {
-                match occlusion_type {
-                    OcclusionType::Raycast => {
-                        direct_sound_path.occlusion =
-                            Self::raycast_occlusion(scene, listener.origin, source.origin);
-                    }
-                    OcclusionType::Volumetric => {
-                        direct_sound_path.occlusion = self.raycast_volumetric(
-                            scene,
-                            listener.origin,
-                            source.origin,
-                            occlusion_radius,
-                            num_occlusion_samples,
-                        );
-                    }
+                direct_sound_path.occlusion = occlusion_model.evaluate(scene, listener, source);
+            }
+
+            if flags.diffraction {
+                if flags.occlusion && direct_sound_path.occlusion < 1.0 {
+                    Self::diffraction(
+                        scene,
+                        listener.origin,
+                        source.origin,
+                        diffraction_model,
+                        &mut direct_sound_path.diffraction,
+                    );
+                } else {
+                    direct_sound_path.diffraction.fill(0.0);
                 }
             }
 
             if flags.transmission {
-                self.transmission(
+                transmission_model.evaluate(
                     scene,
-                    listener.origin,
-                    source.origin,
+                    listener,
+                    source,
                     &mut direct_sound_path.transmission,
-                    num_transmission_rays,
                 );
             }
         } else {
             direct_sound_path.occlusion = 1.0;
             direct_sound_path.transmission.fill(1.0);
+            direct_sound_path.diffraction.fill(0.0);
         }
     }
 
-    fn direct_path_delay(listener: Vec3, source: Vec3) -> f32 {
-        (source - listener).length() / SPEED_OF_SOUND
-    }
-
     fn raycast_occlusion(scene: &Scene, listener_position: Vec3, source_position: Vec3) -> f32 {
         match scene.is_occluded(listener_position, source_position) {
             false => 1.0,
@@ -178,6 +351,58 @@ impl DirectSimulator {
         }
     }
 
+    /// Volumetric occlusion using the first `num_samples` sphere volume samples.
+    fn raycast_volumetric(
+        &self,
+        scene: &Scene,
+        listener: Sphere,
+        source: Sphere,
+        num_samples: usize,
+    ) -> f32 {
+        let num_samples = self.sphere_volume_samples.len().min(num_samples);
+
+        self.volumetric_occlusion(scene, listener, source, 0..num_samples)
+            .unwrap_or(0.0)
+    }
+
+    /// Traces `num_samples` of the sphere volume samples, starting where the previous update
+    /// stopped, and blends the result into the accumulated occlusion stored in `state`.
+    fn raycast_volumetric_progressive(
+        &self,
+        scene: &Scene,
+        listener: Sphere,
+        source: Sphere,
+        num_samples: usize,
+        smoothing: f32,
+        state: &mut OcclusionState,
+    ) -> f32 {
+        let total_samples = self.sphere_volume_samples.len();
+        if total_samples == 0 {
+            return state.occlusion.unwrap_or(0.0);
+        }
+
+        let num_samples = total_samples.min(num_samples);
+        let start = state.next_sample % total_samples;
+        state.next_sample = (start + num_samples) % total_samples;
+
+        let indices = (start..start + num_samples).map(|index| index % total_samples);
+        let occlusion = self.volumetric_occlusion(scene, listener, source, indices);
+
+        // If none of the samples in this subset were valid, keep the previous estimate.
+        let accumulated = match (state.occlusion, occlusion) {
+            (Some(previous), Some(occlusion)) => {
+                let smoothing = smoothing.clamp(0.0, 1.0);
+                smoothing * previous + (1.0 - smoothing) * occlusion
+            }
+            (None, Some(occlusion)) => occlusion,
+            (Some(previous), None) => previous,
+            (None, None) => return 0.0,
+        };
+
+        state.occlusion = Some(accumulated);
+        accumulated
+    }
+
     /// Each source has a radius, and several points are sampled within the volume
     /// of this sphere. To calculate a source's volumetric occlusion factor, we first
     /// count the number of samples that are visible to the source. (If the source is
@@ -188,133 +413,123 @@ impl DirectSimulator {
     /// boundaries.) For each sample that's visible to the source, we check whether
     /// it's also visible to the listener. The fraction of samples visible to the
     /// source that are also visible to the listener is then the occlusion factor.
-    fn raycast_volumetric(
+    ///
+    /// If the listener has a radius as well, every source sample is paired with a listener
+    /// sample, and a pair only counts if both samples are valid. The occlusion factor is then
+    /// the fraction of valid pairs that can see each other. The listener uses the samples in
+    /// reverse order, so that the lines between the pairs are not all parallel.
+    ///
+    /// Returns `None` if there are no valid samples.
+    fn volumetric_occlusion(
         &self,
         scene: &Scene,
-        listener_position: Vec3,
-        source_position: Vec3,
-        source_radius: f32,
-        num_samples: usize,
-    ) -> f32 {
+        listener: Sphere,
+        source: Sphere,
+        sample_indices: impl IntoIterator<Item = usize>,
+    ) -> Option<f32> {
         let mut occlusion: f32 = 0.0;
         let mut num_valid_samples = 0;
 
-        let num_samples = self.sphere_volume_samples.len().min(num_samples);
+        let total_samples = self.sphere_volume_samples.len();
 
-        for i in 0..num_samples {
-            let sphere = Sphere::new(source_position, source_radius);
-            let sample = transform_sphere_volume_sample(self.sphere_volume_samples[i], sphere);
+        for index in sample_indices {
+            let source_sample =
+                transform_sphere_volume_sample(self.sphere_volume_samples[index], source);
 
-            if scene.is_occluded(source_position, sample) {
+            if scene.is_occluded(source.center, source_sample) {
                 continue;
             }
 
+            let listener_sample = if listener.radius > 0.0 {
+                let sample = transform_sphere_volume_sample(
+                    self.sphere_volume_samples[total_samples - 1 - index],
+                    listener,
+                );
+
+                if scene.is_occluded(listener.center, sample) {
+                    continue;
+                }
+
+                sample
+            } else {
+                listener.center
+            };
+
             num_valid_samples += 1;
 
-            if !scene.is_occluded(listener_position, sample) {
+            if !scene.is_occluded(listener_sample, source_sample) {
                 occlusion += 1.0;
             }
         }
 
         if num_valid_samples == 0 {
-            return 0.0;
+            return None;
         }
 
-        occlusion / num_valid_samples as f32
+        Some(occlusion / num_valid_samples as f32)
     }
 
-    fn transmission(
-        &self,
+    /// Finds the edge of the scene geometry that gives the shortest unoccluded path from the
+    /// source, over the edge, to the listener, and evaluates the diffraction model for it. If no
+    /// such edge exists, the diffraction factors are set to zero.
+    ///
+    /// The diffraction point on each edge is approximated by the point closest to the straight
+    /// line between the source and the listener.
+    fn diffraction(
         scene: &Scene,
         listener_position: Vec3,
         source_position: Vec3,
-        transmission_factors: &mut [f32],
-        num_transmission_rays: usize,
+        diffraction_model: &(impl DiffractionModel + ?Sized),
+        diffraction_factors: &mut [f32; NUM_BANDS],
     ) {
-        // todo: Warn instead?
-        if num_transmission_rays == 0 {
-            return;
-        }
-
-        // If, after finding a hit point, we want to continue tracing the ray towards the
-        // source, then offset the ray origin by this distance along the ray direction, to
-        // prevent self-intersection.
-        let ray_offset = 1e-2f32;
-
-        // todo: I'm not sure I understand the following. Might be worth investigating.
-        // We will alternate between tracing a ray from the listener to the source, and from the source to the listener.
-        // The motivation is that if the listener observes the source go behind an object, then that object's material is
-        // most relevant in terms of the expected amount of transmitted sound, even if there are multiple other occluders
-        // between the source and the listener.
-        let rays = [
-            Ray::new(
-                listener_position,
-                (source_position - listener_position).normalize(),
-            ),
-            Ray::new(
-                source_position,
-                (listener_position - source_position).normalize(),
-            ),
-        ];
-
-        let mut current_ray_index = 0;
-        let mut hit_count = 0;
-        let mut min_distances: [f32; 2] = [0.0, 0.0];
-        let max_distance = (source_position - listener_position).length();
-
-        // Product of the transmission coefficients of all hit points.
-        let mut accumulated_transmission: [f32; NUM_BANDS] = [1.0, 1.0, 1.0];
-
-        for _ in 0..num_transmission_rays {
-            // Select the ray we want to trace for this iteration.
-            let ray = &rays[current_ray_index];
-            let min_distance = &mut min_distances[current_ray_index];
-
-            let hit = scene.closest_hit(ray, *min_distance, max_distance);
-
-            let hit = match hit {
-                Some(hit) => hit,
-                // If there's nothing more between the ray origin and the source, stop.
-                None => break,
-            };
-
-            hit_count += 1;
-
-            // Accumulate the product of the transmission coefficients of all materials
-            // encountered so far.
-            for j in 0..NUM_BANDS {
-                accumulated_transmission[j] *= hit.material.transmission[j];
+        /// Maximum number of candidate edges that are checked for visibility.
+        const MAX_CANDIDATES: usize = 16;
+
+        // The diffraction point lies on the geometry, so it is moved slightly away from it
+        // before checking visibility.
+        const EDGE_OFFSET: f32 = 1e-2;
+
+        diffraction_factors.fill(0.0);
+
+        let direct_distance = (source_position - listener_position).length();
+        let line_direction = (source_position - listener_position).normalize_or_zero();
+
+        let mut candidates: Vec<(Vec3, f32)> = scene
+            .diffraction_edges()
+            .iter()
+            .map(|edge| {
+                let point = edge.closest_point_to_segment(listener_position, source_position);
+                let path_length =
+                    (point - listener_position).length() + (source_position - point).length();
+                (point, path_length - direct_distance)
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        for (point, path_difference) in candidates.into_iter().take(MAX_CANDIDATES) {
+            // Push the point away from the line between the source and the listener, which is
+            // towards the unoccluded side of the edge.
+            let from_line = point - listener_position;
+            let offset =
+                (from_line - from_line.dot(line_direction) * line_direction).normalize_or_zero();
+            if offset == Vec3::ZERO {
+                continue;
             }
 
-            // Calculate the origin of the next ray segment we'll trace, if any.
-            *min_distance = hit.distance + ray_offset;
-            if *min_distance >= max_distance {
-                break;
-            }
+            let point = point + offset * EDGE_OFFSET;
 
-            // If the total distance traveled by both rays is greater than the distance between
-            // the source and the listener, then the rays have crossed, so stop.
-            if (min_distances[0] + min_distances[1]) >= max_distance {
-                break;
+            if scene.is_occluded(listener_position, point)
+                || scene.is_occluded(point, source_position)
+            {
+                continue;
             }
 
-            // Switch to the other ray for the next iteration.
-            current_ray_index = 1 - current_ray_index;
-        }
-
-        if hit_count <= 1 {
-            // If we have only 1 hit, then use the transmission coefficients of that material.
-            // If we have no hits, this will automatically set the transmission coefficients to
-            // [1.0, 1.0, 1.0] (i.e., 100% transmission).
-            transmission_factors.copy_from_slice(accumulated_transmission.as_slice());
-        } else {
-            // We have more than one hit, so set the total transmission to the square root of the
-            // product of the transmission coefficients of all hit points. This assumes that hit
-            // points occur in pairs, e.g. both sides of a solid wall, in which case we avoid
-            // double-counting the transmission due to both sides of the wall.
-            for i in 0..NUM_BANDS {
-                transmission_factors[i] = accumulated_transmission[i].sqrt();
+            for (band, factor) in diffraction_factors.iter_mut().enumerate() {
+                *factor = diffraction_model.evaluate(path_difference, band);
             }
+
+            return;
         }
     }
 }
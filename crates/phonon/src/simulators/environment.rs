@@ -0,0 +1,147 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::dsp::bands::NUM_BANDS;
+use crate::dsp::reverb_estimator::RoomProperties;
+use crate::scene::Scene;
+use crate::scene::coordinate_space::CoordinateSpace3f;
+use crate::scene::ray::Ray;
+use crate::scene::sampling::generate_sphere_surface_samples;
+use glam::Vec3;
+use std::f32::consts::PI;
+
+/// Summary of the surroundings of a point, as seen by a fan of rays cast in all directions.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EnvironmentSummary {
+    /// Approximate volume of the enclosed space around the point, in cubic meters.
+    /// Only rays that hit geometry contribute, so an open door does not make a room infinitely big.
+    /// The directions of escaping rays count as empty, so a room without a ceiling comes out
+    /// smaller than the same room with one.
+    pub room_volume: f32,
+    /// Approximate surface area of the geometry enclosing the point, in square meters.
+    pub surface_area: f32,
+    /// Average distance sound travels between two reflections, in meters.
+    pub mean_free_path: f32,
+    /// Area-weighted average absorption coefficient of the materials that were hit, per band.
+    pub absorption: [f32; NUM_BANDS],
+    /// Fraction of rays that did not hit anything within the maximum distance.
+    /// 0.0 means fully enclosed, 1.0 means out in the open.
+    pub outdoorness: f32,
+}
+
+impl EnvironmentSummary {
+    /// Room properties that can be used to estimate reverb times with the `ReverbEstimator`.
+    pub fn room_properties(&self) -> RoomProperties {
+        RoomProperties {
+            volume: self.room_volume,
+            surface_area: self.surface_area,
+            absorption: self.absorption,
+        }
+    }
+}
+
+/// Casts a fixed set of rays around a point to find out what kind of space the point is in.
+/// Nothing needs to be baked, so this is cheap enough to run for the listener every frame, as long
+/// as the number of rays is kept reasonable.
+///
+/// Each ray represents an equal solid angle. The volume is estimated by treating the space as a
+/// star-shaped region around the point, and the surface area by projecting each solid angle onto
+/// the surface that was hit.
+pub struct EnvironmentSimulator {
+    /// Ray directions, evenly distributed over the unit sphere.
+    ray_directions: Vec<Vec3>,
+}
+
+impl EnvironmentSimulator {
+    pub fn new(num_rays: usize) -> Self {
+        Self {
+            ray_directions: generate_sphere_surface_samples(num_rays),
+        }
+    }
+
+    pub fn simulate(
+        &self,
+        scene: &Scene,
+        listener: &CoordinateSpace3f,
+        max_distance: f32,
+        summary: &mut EnvironmentSummary,
+    ) {
+        // Limits how much a single grazing hit can contribute to the surface area.
+        const MIN_COSINE: f32 = 0.1;
+
+        let mut num_hits = 0;
+        let mut volume = 0.0;
+        let mut surface_area = 0.0;
+        let mut absorption = [0.0f32; NUM_BANDS];
+
+        for direction in &self.ray_directions {
+            let ray = Ray::new(listener.origin, *direction);
+
+            let Some(hit) = scene.closest_hit(&ray, 0.0, max_distance) else {
+                continue;
+            };
+
+            num_hits += 1;
+
+            let distance = hit.distance;
+            let cosine = hit.normal.dot(*direction).abs().max(MIN_COSINE);
+            let area = distance * distance / cosine;
+
+            volume += distance * distance * distance / 3.0;
+            surface_area += area;
+
+            for band in 0..NUM_BANDS {
+                absorption[band] += area * hit.material.absorption[band];
+            }
+        }
+
+        let num_rays = self.ray_directions.len();
+        summary.outdoorness = if num_rays > 0 {
+            1.0 - num_hits as f32 / num_rays as f32
+        } else {
+            0.0
+        };
+
+        if num_hits == 0 {
+            summary.room_volume = 0.0;
+            summary.surface_area = 0.0;
+            summary.mean_free_path = max_distance;
+            summary.absorption = [0.0; NUM_BANDS];
+            return;
+        }
+
+        // Solid angle represented by each ray. Rays that escaped add nothing, so a partly open
+        // space is not extrapolated to what it would be if it were closed all around.
+        let solid_angle = 4.0 * PI / num_rays as f32;
+
+        summary.room_volume = volume * solid_angle;
+        summary.surface_area = surface_area * solid_angle;
+        summary.mean_free_path = if summary.surface_area > 0.0 {
+            4.0 * summary.room_volume / summary.surface_area
+        } else {
+            max_distance
+        };
+
+        for band in 0..NUM_BANDS {
+            summary.absorption[band] = if surface_area > 0.0 {
+                absorption[band] / surface_area
+            } else {
+                0.0
+            };
+        }
+    }
+}
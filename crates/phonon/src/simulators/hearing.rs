@@ -0,0 +1,94 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::dsp::bands::NUM_BANDS;
+use crate::effects::direct::{DirectEffect, TransmissionType};
+use crate::scene::coordinate_space::CoordinateSpace3f;
+use crate::simulators::simulator::{Listener, ListenerResult, Simulator, Source};
+use glam::Vec3;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Asks how loud a source is at some position, for example to let an NPC hear the player.
+pub struct HearingQuery<'a> {
+    /// The sound that might be heard. Its models and settings are used just like when it is
+    /// simulated for a listener.
+    pub source: &'a Source,
+    /// Where the sound is heard, for example the head of an NPC.
+    pub position: Vec3,
+    /// Level of the source in dB, as it would be measured at the reference distance of its
+    /// distance attenuation model. Footsteps are around 50 dB, a gunshot over 140 dB.
+    pub level: f32,
+}
+
+/// How a source is heard at the position of a `HearingQuery`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HearingResult {
+    /// Fraction of the emitted sound that reaches the position, per band. Includes everything
+    /// the flags of the source enable: distance attenuation, air absorption, directivity,
+    /// occlusion, transmission and diffraction.
+    pub gains: [f32; NUM_BANDS],
+    /// Perceived level per band, in dB.
+    pub levels: [f32; NUM_BANDS],
+    /// Time the sound takes to arrive, in seconds.
+    pub delay: f32,
+}
+
+impl HearingResult {
+    /// Level of the loudest band, in dB. Compare this against a hearing threshold to decide
+    /// whether the sound is noticed.
+    pub fn max_level(&self) -> f32 {
+        self.levels.into_iter().fold(f32::NEG_INFINITY, f32::max)
+    }
+}
+
+impl Simulator {
+    /// Returns how loud the source of `query` is at its position. Uses the committed scene and
+    /// the same models as `run_direct`, but does not render any audio, nor does it change the
+    /// cached results of the source.
+    pub fn hear(&self, query: &HearingQuery) -> HearingResult {
+        let listener = Listener::new(CoordinateSpace3f::from_origin(query.position));
+        let mut result = ListenerResult::default();
+        self.simulate_direct_for_listener(query.source, &listener, &mut result);
+
+        let path = result.direct_sound_path;
+        let gains = DirectEffect::band_gains(
+            path,
+            query.source.settings.flags,
+            TransmissionType::FrequencyDependent,
+        );
+
+        HearingResult {
+            gains,
+            levels: gains.map(|gain| query.level + 20.0 * gain.max(f32::MIN_POSITIVE).log10()),
+            delay: path.delay,
+        }
+    }
+
+    /// Answers many hearing queries at once, for example one per NPC. The results are in the
+    /// same order as the queries.
+    ///
+    /// With the `rayon` feature enabled, queries are answered in parallel on the global rayon
+    /// thread pool.
+    pub fn hear_all(&self, queries: &[HearingQuery]) -> Vec<HearingResult> {
+        #[cfg(feature = "rayon")]
+        return queries.par_iter().map(|query| self.hear(query)).collect();
+
+        #[cfg(not(feature = "rayon"))]
+        queries.iter().map(|query| self.hear(query)).collect()
+    }
+}
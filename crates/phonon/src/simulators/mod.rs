@@ -2,3 +2,9 @@
 //! based on the `scene` and `models`.
 
 pub mod direct;
+pub mod environment;
+pub mod hearing;
+pub mod pathing;
+pub mod reflection;
+pub mod simulator;
+pub mod source_shape;
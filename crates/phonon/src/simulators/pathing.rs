@@ -0,0 +1,271 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::dsp::bands::NUM_BANDS;
+use crate::models::air_absorption::AirAbsorptionModel;
+use crate::models::deviation::DeviationModel;
+use crate::models::distance_attenuation::DistanceAttenuationModel;
+use crate::scene::Scene;
+use crate::scene::coordinate_space::CoordinateSpace3f;
+use glam::Vec3;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// A graph of points in the scene, where two points are connected if they can see each other.
+/// The points are usually probes placed throughout the walkable areas of a level. Building the
+/// graph is expensive, so it should be done once for static geometry.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct VisibilityGraph {
+    nodes: Vec<Vec3>,
+    /// For each node, the indices of the nodes it can see and the distance to them.
+    adjacency: Vec<Vec<(usize, f32)>>,
+}
+
+impl VisibilityGraph {
+    /// Creates a visibility graph between `nodes`. Nodes further apart than `max_edge_length` are
+    /// never connected, even if they can see each other.
+    pub fn new(scene: &Scene, nodes: Vec<Vec3>, max_edge_length: f32) -> Self {
+        let mut adjacency = vec![Vec::new(); nodes.len()];
+
+        for i in 0..nodes.len() {
+            for j in (i + 1)..nodes.len() {
+                let distance = (nodes[j] - nodes[i]).length();
+
+                if distance > max_edge_length || scene.is_occluded(nodes[i], nodes[j]) {
+                    continue;
+                }
+
+                adjacency[i].push((j, distance));
+                adjacency[j].push((i, distance));
+            }
+        }
+
+        Self { nodes, adjacency }
+    }
+
+    pub fn nodes(&self) -> &[Vec3] {
+        &self.nodes
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.adjacency.iter().map(Vec::len).sum::<usize>() / 2
+    }
+}
+
+/// Describes the shortest unoccluded path sound can take from the source to the listener.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathingSoundPath {
+    /// Whether a path was found at all. If not, the other fields should be ignored.
+    pub path_found: bool,
+    /// Total length of the path, in meters.
+    pub distance: f32,
+    /// Per-band gain, including distance attenuation, air absorption and the attenuation caused
+    /// by bending around corners.
+    pub eq_coefficients: [f32; NUM_BANDS],
+    /// Unit vector pointing from the listener in the direction the sound arrives from. For an
+    /// occluded source this points towards the opening the sound comes through.
+    pub direction: Vec3,
+    /// Position the sound appears to come from: `distance` meters away from the listener along
+    /// `direction`.
+    pub virtual_source: Vec3,
+}
+
+impl Default for PathingSoundPath {
+    fn default() -> Self {
+        Self {
+            path_found: false,
+            distance: 0.0,
+            eq_coefficients: [0.0; NUM_BANDS],
+            direction: Vec3::NEG_Z,
+            virtual_source: Vec3::ZERO,
+        }
+    }
+}
+
+/// Finds paths around obstacles between sources and the listener, using a `VisibilityGraph`.
+///
+/// If the source is directly visible from the listener, the direct line is used. Otherwise, the
+/// source and the listener are temporarily connected to all graph nodes they can see within
+/// `visibility_radius`, and the shortest path between them is found using Dijkstra's algorithm.
+pub struct PathingSimulator {
+    graph: VisibilityGraph,
+    /// Maximum distance at which the source and listener get connected to the graph.
+    pub visibility_radius: f32,
+}
+
+impl PathingSimulator {
+    pub fn new(graph: VisibilityGraph, visibility_radius: f32) -> Self {
+        Self {
+            graph,
+            visibility_radius,
+        }
+    }
+
+    pub fn graph(&self) -> &VisibilityGraph {
+        &self.graph
+    }
+
+    #[expect(clippy::too_many_arguments)]
+    pub fn simulate(
+        &self,
+        scene: &Scene,
+        source: &CoordinateSpace3f,
+        listener: &CoordinateSpace3f,
+        distance_attenuation_model: &impl DistanceAttenuationModel,
+        air_absorption_model: &impl AirAbsorptionModel,
+        deviation_model: &impl DeviationModel,
+        sound_path: &mut PathingSoundPath,
+    ) {
+        let Some(points) = self.find_path(scene, source.origin, listener.origin) else {
+            *sound_path = PathingSoundPath::default();
+            return;
+        };
+
+        let distance: f32 = points
+            .windows(2)
+            .map(|segment| (segment[1] - segment[0]).length())
+            .sum();
+
+        let mut eq_coefficients = [distance_attenuation_model.evaluate(distance); NUM_BANDS];
+        for band in 0..NUM_BANDS {
+            eq_coefficients[band] *= air_absorption_model.evaluate(distance, band);
+        }
+
+        // Every intermediate point is a corner the sound has to bend around.
+        for corner in points.windows(3) {
+            let incoming = (corner[1] - corner[0]).normalize_or_zero();
+            let outgoing = (corner[2] - corner[1]).normalize_or_zero();
+            let angle = incoming.dot(outgoing).clamp(-1.0, 1.0).acos();
+
+            for band in 0..NUM_BANDS {
+                eq_coefficients[band] *= deviation_model.evaluate(angle, band);
+            }
+        }
+
+        // The path is stored from the listener to the source.
+        let direction = (points[1] - points[0]).normalize_or(Vec3::NEG_Z);
+
+        *sound_path = PathingSoundPath {
+            path_found: true,
+            distance,
+            eq_coefficients,
+            direction,
+            virtual_source: listener.origin + direction * distance,
+        };
+    }
+
+    /// Returns the points along the shortest path, starting at the listener and ending at the
+    /// source, or `None` if there is no path.
+    fn find_path(&self, scene: &Scene, source: Vec3, listener: Vec3) -> Option<Vec<Vec3>> {
+        if !scene.is_occluded(listener, source) {
+            return Some(vec![listener, source]);
+        }
+
+        let nodes = self.graph.nodes();
+        let visible_nodes = |point: Vec3| -> Vec<(usize, f32)> {
+            nodes
+                .iter()
+                .enumerate()
+                .map(|(index, node)| (index, (*node - point).length()))
+                .filter(|(index, distance)| {
+                    *distance <= self.visibility_radius && !scene.is_occluded(point, nodes[*index])
+                })
+                .collect()
+        };
+
+        let source_nodes = visible_nodes(source);
+        if source_nodes.is_empty() {
+            return None;
+        }
+
+        let listener_nodes = visible_nodes(listener);
+
+        // Dijkstra's algorithm, starting from all nodes the listener can see.
+        let mut distances = vec![f32::INFINITY; nodes.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; nodes.len()];
+        let mut queue = BinaryHeap::new();
+
+        for (index, distance) in listener_nodes {
+            distances[index] = distance;
+            queue.push(QueueEntry { distance, index });
+        }
+
+        while let Some(QueueEntry { distance, index }) = queue.pop() {
+            if distance > distances[index] {
+                continue;
+            }
+
+            for &(neighbor, edge_length) in &self.graph.adjacency[index] {
+                let new_distance = distance + edge_length;
+                if new_distance < distances[neighbor] {
+                    distances[neighbor] = new_distance;
+                    previous[neighbor] = Some(index);
+                    queue.push(QueueEntry {
+                        distance: new_distance,
+                        index: neighbor,
+                    });
+                }
+            }
+        }
+
+        let (last_node, _) = source_nodes
+            .into_iter()
+            .map(|(index, distance)| (index, distances[index] + distance))
+            .filter(|(_, distance)| distance.is_finite())
+            .min_by(|a, b| a.1.total_cmp(&b.1))?;
+
+        let mut path = vec![source, nodes[last_node]];
+        let mut current = last_node;
+        while let Some(node) = previous[current] {
+            path.push(nodes[node]);
+            current = node;
+        }
+        path.push(listener);
+        path.reverse();
+
+        Some(path)
+    }
+}
+
+/// Entry in the priority queue used by Dijkstra's algorithm, ordered so that the `BinaryHeap`
+/// pops the smallest distance first.
+#[derive(PartialEq)]
+struct QueueEntry {
+    distance: f32,
+    index: usize,
+}
+
+impl Eq for QueueEntry {}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .distance
+            .total_cmp(&self.distance)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
@@ -0,0 +1,223 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::dsp::bands::NUM_BANDS;
+use crate::dsp::energy_field::{BIN_DURATION, EnergyField};
+use crate::models::air_absorption::AirAbsorptionModel;
+use crate::models::propagation_medium::PropagationMedium;
+use crate::random::{DEFAULT_SEED, rng_from_seed};
+use crate::scene::Scene;
+use crate::scene::coordinate_space::CoordinateSpace3f;
+use crate::scene::ray::Ray;
+use crate::scene::sampling::generate_sphere_surface_samples;
+use glam::Vec3;
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// Simulates listener-centric reverb by tracing rays through the scene.
+///
+/// Rays are emitted from the listener and bounce around the scene, losing energy at every
+/// reflection according to the absorption of the material that was hit. The listener is modelled
+/// as a small sphere, and every time a ray passes through this sphere, its energy is recorded
+/// in the energy field at the time it took to get there. By reciprocity, this is the reverb a
+/// source at the listener position would produce.
+///
+/// Reflections are specular or diffuse (Lambertian), chosen at random according to the
+/// scattering coefficient of the material.
+pub struct ReflectionSimulator {
+    /// Initial ray directions, evenly distributed over the unit sphere.
+    ray_directions: Vec<Vec3>,
+    /// Maximum number of times a ray is reflected.
+    pub num_bounces: usize,
+    /// Radius of the sphere representing the listener, in meters.
+    pub receiver_radius: f32,
+    /// Seed for the random choices made while tracing. Every call to `simulate` starts from
+    /// this seed, so the same inputs always give the same energy field.
+    pub seed: u64,
+    /// Determines how far sound travels within the duration of the energy field.
+    pub medium: PropagationMedium,
+}
+
+impl ReflectionSimulator {
+    pub fn new(num_rays: usize, num_bounces: usize, receiver_radius: f32) -> Self {
+        Self {
+            ray_directions: generate_sphere_surface_samples(num_rays),
+            num_bounces,
+            receiver_radius,
+            seed: DEFAULT_SEED,
+            medium: PropagationMedium::default(),
+        }
+    }
+
+    /// Traces rays from the listener and accumulates the reflected energy in `energy_field`.
+    /// The energy field is reset first. Energy is expressed as energy density at the listener,
+    /// for a source at the listener position that emits one unit of energy.
+    ///
+    /// If the energy field has 4 or more channels, the first-order Ambisonic channels are filled
+    /// as well, in the local coordinate space of the listener. Higher orders are left at zero.
+    pub fn simulate(
+        &self,
+        scene: &Scene,
+        listener: &CoordinateSpace3f,
+        air_absorption_model: &impl AirAbsorptionModel,
+        energy_field: &mut EnergyField,
+    ) {
+        energy_field.reset();
+
+        let num_rays = self.ray_directions.len();
+        if num_rays == 0 || energy_field.num_bins() == 0 {
+            return;
+        }
+
+        let max_distance = energy_field.duration() * self.medium.speed_of_sound;
+        let receiver_volume = 4.0 / 3.0 * PI * self.receiver_radius.powi(3);
+        let has_directions = energy_field.num_channels() >= 4;
+
+        let mut rng = rng_from_seed(self.seed);
+
+        for initial_direction in &self.ray_directions {
+            let mut energy = [1.0 / num_rays as f32; NUM_BANDS];
+            let mut origin = listener.origin;
+            let mut direction = *initial_direction;
+            let mut distance_traveled = 0.0;
+
+            for bounce in 0..=self.num_bounces {
+                let remaining_distance = max_distance - distance_traveled;
+                if remaining_distance <= 0.0 {
+                    break;
+                }
+
+                let ray = Ray::new(origin, direction);
+                let hit = scene.closest_hit(&ray, 0.0, remaining_distance);
+                let segment_length = hit.map_or(remaining_distance, |hit| hit.distance);
+
+                // The first segment starts inside the receiver, that is the direct sound.
+                if bounce > 0 {
+                    self.record(
+                        energy_field,
+                        listener,
+                        origin,
+                        direction,
+                        segment_length,
+                        distance_traveled,
+                        &energy,
+                        receiver_volume,
+                        has_directions,
+                    );
+                }
+
+                let Some(hit) = hit else {
+                    break;
+                };
+
+                distance_traveled += hit.distance;
+
+                // Offset the new origin slightly to prevent self-intersection.
+                let normal = if hit.normal.dot(direction) > 0.0 {
+                    -hit.normal
+                } else {
+                    hit.normal
+                };
+                origin = ray.point_at_distance(hit.distance) + normal * 1e-3;
+
+                for band in 0..NUM_BANDS {
+                    let air_gain = air_absorption_model.evaluate(hit.distance, band);
+                    energy[band] *= (1.0 - hit.material.absorption[band]) * air_gain * air_gain;
+                }
+
+                direction = if rng.random::<f32>() < hit.material.scattering {
+                    Self::diffuse_direction(normal, &mut rng)
+                } else {
+                    direction - 2.0 * direction.dot(normal) * normal
+                };
+            }
+        }
+    }
+
+    /// Adds the energy of a ray segment that passes through the receiver sphere.
+    #[expect(clippy::too_many_arguments)]
+    fn record(
+        &self,
+        energy_field: &mut EnergyField,
+        listener: &CoordinateSpace3f,
+        origin: Vec3,
+        direction: Vec3,
+        segment_length: f32,
+        distance_traveled: f32,
+        energy: &[f32; NUM_BANDS],
+        receiver_volume: f32,
+        has_directions: bool,
+    ) {
+        // Intersect the segment with the receiver sphere.
+        let to_center = listener.origin - origin;
+        let projection = to_center.dot(direction);
+        let distance_squared = to_center.length_squared() - projection * projection;
+        let radius_squared = self.receiver_radius * self.receiver_radius;
+
+        if distance_squared >= radius_squared {
+            return;
+        }
+
+        let half_chord = (radius_squared - distance_squared).sqrt();
+        let entry = (projection - half_chord).max(0.0);
+        let exit = (projection + half_chord).min(segment_length);
+        if exit <= entry {
+            return;
+        }
+
+        let arrival_distance = distance_traveled + projection.clamp(entry, exit);
+        let bin = (self.medium.travel_time(arrival_distance) / BIN_DURATION) as usize;
+        if bin >= energy_field.num_bins() {
+            return;
+        }
+
+        let weight = (exit - entry) / receiver_volume;
+
+        // Sound arrives from the opposite direction of travel.
+        let arrival_direction = listener.direction_to_local(-direction);
+
+        for band in 0..NUM_BANDS {
+            let contribution = energy[band] * weight;
+            energy_field.data[[0, band, bin]] += contribution;
+
+            if has_directions {
+                // First-order Ambisonics in ACN order: Y, Z, X.
+                energy_field.data[[1, band, bin]] += contribution * arrival_direction.y;
+                energy_field.data[[2, band, bin]] += contribution * arrival_direction.z;
+                energy_field.data[[3, band, bin]] += contribution * arrival_direction.x;
+            }
+        }
+    }
+
+    /// Cosine-weighted random direction in the hemisphere around `normal`.
+    fn diffuse_direction(normal: Vec3, rng: &mut impl Rng) -> Vec3 {
+        let u: f32 = rng.random();
+        let v: f32 = rng.random();
+
+        let radius = u.sqrt();
+        let phi = 2.0 * PI * v;
+
+        let space = CoordinateSpace3f::from_vector(normal, Vec3::ZERO);
+        let local = Vec3::new(
+            radius * phi.cos(),
+            radius * phi.sin(),
+            -(1.0 - u).max(0.0).sqrt(),
+        );
+
+        space.direction_to_world(local)
+    }
+}
@@ -0,0 +1,560 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::dsp::bands::NUM_BANDS;
+use crate::effects::direct::DirectApplyFlags;
+use crate::models::air_absorption::AirAbsorptionModel;
+use crate::models::diffraction::{DefaultDiffractionModel, DiffractionModel};
+use crate::models::directivity::{Directivity, DirectivityModel};
+use crate::models::distance_attenuation::{
+    DefaultDistanceAttenuationModel, DistanceAttenuationModel,
+};
+use crate::models::occlusion::OcclusionModel;
+use crate::models::propagation_medium::PropagationMedium;
+use crate::models::transmission::{RaycastTransmissionModel, TransmissionModel};
+use crate::scene::Scene;
+use crate::scene::coordinate_space::CoordinateSpace3f;
+use crate::simulators::direct::{DirectSimulator, DirectSoundPath, OcclusionState, OcclusionType};
+use crate::simulators::source_shape::{ShapeAttenuation, SourceShape};
+use glam::Vec3;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::sync::{Arc, Mutex};
+
+/// Per-source settings for the direct sound simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceSettings {
+    /// Which parts of the direct sound path are simulated.
+    pub flags: DirectApplyFlags,
+    pub occlusion_type: OcclusionType,
+    /// Size of the source when `occlusion_type` is set to `Volumetric`.
+    pub occlusion_radius: f32,
+    /// Number of samples used when `occlusion_type` is set to `Volumetric`.
+    /// Limited by `max_occlusion_samples` of the `Simulator`.
+    pub num_occlusion_samples: usize,
+    pub num_transmission_rays: usize,
+    /// How distance attenuation and air absorption are evaluated for sources with a shape.
+    pub shape_attenuation: ShapeAttenuation,
+}
+
+impl Default for SourceSettings {
+    fn default() -> Self {
+        Self {
+            flags: DirectApplyFlags::all(),
+            occlusion_type: OcclusionType::Raycast,
+            occlusion_radius: 1.0,
+            num_occlusion_samples: 16,
+            num_transmission_rays: 3,
+            shape_attenuation: ShapeAttenuation::Nearest,
+        }
+    }
+}
+
+/// How much sources and the listener may move before cached simulation results are discarded.
+/// Tolerances are measured from the state the cached result was computed with, so slow movement
+/// still triggers a new simulation eventually.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheTolerances {
+    /// Maximum change in position, in meters.
+    pub position: f32,
+    /// Maximum change in orientation, in radians.
+    pub orientation: f32,
+}
+
+impl Default for CacheTolerances {
+    fn default() -> Self {
+        Self {
+            position: 0.01,
+            orientation: 0.01,
+        }
+    }
+}
+
+/// Position and orientation of a source or listener, as used to validate cached results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Pose {
+    origin: Vec3,
+    ahead: Vec3,
+    up: Vec3,
+}
+
+impl Pose {
+    fn new(coordinates: &CoordinateSpace3f) -> Self {
+        Self {
+            origin: coordinates.origin,
+            ahead: coordinates.ahead,
+            up: coordinates.up,
+        }
+    }
+
+    fn is_close_to(&self, other: &Pose, tolerances: &CacheTolerances) -> bool {
+        let angle = |a: Vec3, b: Vec3| a.dot(b).clamp(-1.0, 1.0).acos();
+
+        (self.origin - other.origin).length() <= tolerances.position
+            && angle(self.ahead, other.ahead) <= tolerances.orientation
+            && angle(self.up, other.up) <= tolerances.orientation
+    }
+}
+
+/// A point sound is heard from, such as the player of a split-screen game.
+#[derive(Debug, Default)]
+pub struct Listener {
+    pub coordinates: CoordinateSpace3f,
+    /// Size of the listener when sources use volumetric occlusion. Zero treats the listener as
+    /// a point.
+    pub radius: f32,
+}
+
+impl Listener {
+    pub fn new(coordinates: CoordinateSpace3f) -> Self {
+        Self {
+            coordinates,
+            radius: 0.0,
+        }
+    }
+}
+
+/// Everything the latest direct simulation result of a source depends on, apart from its models.
+#[derive(Debug, Clone, PartialEq)]
+struct DirectCacheKey {
+    source: Pose,
+    listener: Pose,
+    listener_radius: f32,
+    settings: SourceSettings,
+    shape: SourceShape,
+    scene_version: u32,
+}
+
+/// Results of the direct simulation of a source for one listener.
+#[derive(Default)]
+pub struct ListenerResult {
+    pub direct_sound_path: DirectSoundPath,
+    /// Where the sound of the source appears to come from. For a point source this is its
+    /// origin, for other shapes the center of the part the listener can see. Spatialize the
+    /// source towards this point.
+    pub apparent_position: Vec3,
+    /// State `direct_sound_path` was computed with.
+    direct_cache_key: Option<DirectCacheKey>,
+    occlusion_state: OcclusionState,
+}
+
+/// A sound source known to the `Simulator`. It carries everything needed to simulate it, and
+/// receives the results of the simulation for every listener.
+pub struct Source {
+    pub coordinates: CoordinateSpace3f,
+    /// Extent of the source. For shapes other than a point, the direct sound path is simulated
+    /// from the point closest to the listener, and occlusion is the visible fraction of the
+    /// shape. `occlusion_type` is then ignored.
+    pub shape: SourceShape,
+    pub settings: SourceSettings,
+    pub distance_attenuation_model: Box<dyn DistanceAttenuationModel + Send + Sync>,
+    /// Replaces the absorption of the medium set on the `Simulator`.
+    pub air_absorption_model: Option<Box<dyn AirAbsorptionModel + Send + Sync>>,
+    /// Replaces a `DefaultDiffractionModel` in the medium set on the `Simulator`.
+    pub diffraction_model: Option<Box<dyn DiffractionModel + Send + Sync>>,
+    pub directivity_model: Box<dyn DirectivityModel + Send + Sync>,
+    /// Replaces the ray traced occlusion selected by `settings.occlusion_type`. Call
+    /// `invalidate_cache` when its result changes while the source and listener stand still,
+    /// for example when a door closes.
+    pub occlusion_model: Option<Box<dyn OcclusionModel + Send + Sync>>,
+    /// Replaces the ray traced transmission that uses `settings.num_transmission_rays`. Like
+    /// the occlusion model, changes to its result require a call to `invalidate_cache`.
+    pub transmission_model: Option<Box<dyn TransmissionModel + Send + Sync>>,
+    /// Results of the latest call to `Simulator::run_direct`, in the same order as the
+    /// listeners of the `Simulator`. Empty until the source is simulated.
+    pub listener_results: Vec<ListenerResult>,
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Self {
+            coordinates: CoordinateSpace3f::default(),
+            shape: SourceShape::Point,
+            settings: SourceSettings::default(),
+            distance_attenuation_model: Box::new(DefaultDistanceAttenuationModel::default()),
+            air_absorption_model: None,
+            diffraction_model: None,
+            directivity_model: Box::new(Directivity::default()),
+            occlusion_model: None,
+            transmission_model: None,
+            listener_results: Vec::new(),
+        }
+    }
+}
+
+impl Source {
+    pub fn new(coordinates: CoordinateSpace3f, settings: SourceSettings) -> Self {
+        Self {
+            coordinates,
+            settings,
+            ..Default::default()
+        }
+    }
+
+    /// Forces the next `Simulator::run_direct` to simulate this source, even if nothing seems to
+    /// have changed. Needed after replacing one of the models, which the cache cannot detect.
+    pub fn invalidate_cache(&mut self) {
+        for result in &mut self.listener_results {
+            result.direct_cache_key = None;
+        }
+    }
+
+    /// Discards the occlusion accumulated by `OcclusionType::ProgressiveVolumetric`. Useful when
+    /// the source teleports, so that the old occlusion does not linger.
+    pub fn reset_occlusion(&mut self) {
+        for result in &mut self.listener_results {
+            result.occlusion_state.reset();
+        }
+    }
+}
+
+/// Owns the scene, the listeners and the sources, and runs the simulations for all sources at
+/// once. Every source is simulated against every listener, for example one per player in a
+/// split-screen game. Render a separate mix per listener, or combine the results with
+/// `DirectSoundPath::mix`.
+///
+/// Like the `Scene`, sources added or removed through `add_source` and `remove_source` are only
+/// simulated after `commit` is called. Changes to the sources themselves (their position for
+/// example) are picked up by the next run without committing.
+///
+/// Results are cached per source and listener: a source is only simulated again if it, the
+/// listener or its
+/// settings changed by more than `cache_tolerances`, or if the scene changed. Sources that use
+/// `OcclusionType::ProgressiveVolumetric` keep refining their occlusion, so they are never
+/// cached.
+pub struct Simulator {
+    pub scene: Scene,
+    /// Results of each source are stored in the same order as the listeners. There is a single
+    /// listener at the origin by default.
+    pub listeners: Vec<Listener>,
+    /// Set to `None` to simulate every source on every run.
+    pub cache_tolerances: Option<CacheTolerances>,
+    direct_simulator: DirectSimulator,
+    /// Two lists of sources. The one at index 0 is used for simulation, the one at index 1 can
+    /// be changed by the user. `commit` copies the latter to the former.
+    sources: [Vec<Arc<Mutex<Source>>>; 2],
+}
+
+impl Simulator {
+    /// Creates a simulator with an empty scene. `max_occlusion_samples` limits the number of
+    /// samples any source can use for volumetric occlusion.
+    pub fn new(max_occlusion_samples: usize) -> Self {
+        Self {
+            scene: Scene::new(),
+            listeners: vec![Listener::default()],
+            cache_tolerances: Some(CacheTolerances::default()),
+            direct_simulator: DirectSimulator::new(max_occlusion_samples),
+            sources: [Vec::new(), Vec::new()],
+        }
+    }
+
+    pub fn add_source(&mut self, source: Arc<Mutex<Source>>) {
+        self.sources[1].push(source);
+    }
+
+    pub fn remove_source(&mut self, source: Arc<Mutex<Source>>) {
+        self.sources[1].retain(|x| !Arc::ptr_eq(x, &source));
+    }
+
+    pub fn medium(&self) -> &PropagationMedium {
+        &self.direct_simulator.medium
+    }
+
+    /// Changes the medium sound travels through, for example when the listener dives under
+    /// water. It determines the delay, air absorption and diffraction of every source, unless
+    /// the source has its own air absorption or diffraction model. Cached results of all
+    /// sources are discarded.
+    pub fn set_medium(&mut self, medium: PropagationMedium) {
+        self.direct_simulator.medium = medium;
+
+        for source in self.sources.iter().flatten() {
+            source.lock().unwrap().invalidate_cache();
+        }
+    }
+
+    /// The diffraction model used for sources without their own.
+    fn medium_diffraction_model(&self) -> DefaultDiffractionModel {
+        DefaultDiffractionModel {
+            medium: self.direct_simulator.medium,
+            ..Default::default()
+        }
+    }
+
+    pub fn num_sources(&self) -> usize {
+        self.sources[0].len()
+    }
+
+    /// Applies the changes made to the scene and the list of sources.
+    pub fn commit(&mut self) {
+        self.scene.commit();
+        self.sources[0] = self.sources[1].clone();
+    }
+
+    /// Simulates the direct sound path of every committed source, and stores the result in the
+    /// source.
+    ///
+    /// With the `rayon` feature enabled, sources are simulated in parallel on the global rayon
+    /// thread pool. Each source only reads the committed scene and writes its own result, so the
+    /// results do not depend on the number of threads.
+    pub fn run_direct(&self) {
+        #[cfg(feature = "rayon")]
+        self.sources[0]
+            .par_iter()
+            .for_each(|source| self.simulate_direct(source));
+
+        #[cfg(not(feature = "rayon"))]
+        self.sources[0]
+            .iter()
+            .for_each(|source| self.simulate_direct(source));
+    }
+
+    fn simulate_direct(&self, source: &Mutex<Source>) {
+        let mut source = source.lock().unwrap();
+        let source = &mut *source;
+
+        let mut results = std::mem::take(&mut source.listener_results);
+        results.resize_with(self.listeners.len(), ListenerResult::default);
+
+        for (listener, result) in self.listeners.iter().zip(&mut results) {
+            self.simulate_direct_for_listener(source, listener, result);
+        }
+
+        source.listener_results = results;
+    }
+
+    pub(crate) fn simulate_direct_for_listener(
+        &self,
+        source: &Source,
+        listener: &Listener,
+        result: &mut ListenerResult,
+    ) {
+        let key = DirectCacheKey {
+            source: Pose::new(&source.coordinates),
+            listener: Pose::new(&listener.coordinates),
+            listener_radius: listener.radius,
+            settings: source.settings,
+            shape: source.shape.clone(),
+            scene_version: self.scene.change_version(),
+        };
+
+        let progressive = matches!(
+            source.settings.occlusion_type,
+            OcclusionType::ProgressiveVolumetric { .. }
+        );
+
+        if let (Some(tolerances), Some(cached)) = (&self.cache_tolerances, &result.direct_cache_key)
+            && !progressive
+            && cached.settings == key.settings
+            && cached.shape == key.shape
+            && cached.listener_radius == key.listener_radius
+            && cached.scene_version == key.scene_version
+            && cached.source.is_close_to(&key.source, tolerances)
+            && cached.listener.is_close_to(&key.listener, tolerances)
+        {
+            return;
+        }
+
+        result.direct_cache_key = Some(key);
+
+        if source.shape != SourceShape::Point {
+            self.simulate_shape(source, listener, result);
+            return;
+        }
+
+        result.apparent_position = source.coordinates.origin;
+
+        let ray_traced_occlusion = self
+            .direct_simulator
+            .occlusion_model(
+                source.settings.occlusion_type,
+                source.settings.occlusion_radius,
+                listener.radius,
+                source.settings.num_occlusion_samples,
+            )
+            .with_state(result.occlusion_state);
+        let ray_traced_transmission = RaycastTransmissionModel {
+            num_rays: source.settings.num_transmission_rays,
+        };
+        let medium_diffraction = self.medium_diffraction_model();
+        let occlusion_model: &dyn OcclusionModel = match &source.occlusion_model {
+            Some(model) => model.as_ref(),
+            None => &ray_traced_occlusion,
+        };
+
+        self.direct_simulator.simulate(
+            Some(&self.scene),
+            source.settings.flags,
+            &source.coordinates,
+            &listener.coordinates,
+            source.distance_attenuation_model.as_ref(),
+            match &source.air_absorption_model {
+                Some(model) => model.as_ref(),
+                None => &self.direct_simulator.medium,
+            },
+            match &source.diffraction_model {
+                Some(model) => model.as_ref(),
+                None => &medium_diffraction,
+            },
+            source.directivity_model.as_ref(),
+            occlusion_model,
+            match &source.transmission_model {
+                Some(model) => model.as_ref(),
+                None => &ray_traced_transmission,
+            },
+            &mut result.direct_sound_path,
+        );
+
+        result.occlusion_state = ray_traced_occlusion.state();
+    }
+
+    /// Simulates a source with an extent from the point of its shape that is closest to the
+    /// listener. Occlusion is the visible fraction of the shape and, if requested, the
+    /// attenuation is sampled across the shape. A custom occlusion model is evaluated at the
+    /// closest point instead of sampling the shape.
+    fn simulate_shape(&self, source: &Source, listener: &Listener, result: &mut ListenerResult) {
+        let settings = source.settings;
+        let flags = settings.flags;
+        let coordinates = &source.coordinates;
+        let num_samples = settings.num_occlusion_samples;
+
+        let closest = CoordinateSpace3f {
+            origin: source
+                .shape
+                .closest_point(coordinates, listener.coordinates.origin),
+            ..*coordinates
+        };
+
+        // The visible fraction is passed on as the occlusion, so that diffraction is applied
+        // whenever part of the shape is hidden.
+        let visibility = match (&source.occlusion_model, flags.occlusion) {
+            (None, true) => Some(source.shape.visibility(
+                &self.scene,
+                coordinates,
+                listener.coordinates.origin,
+                num_samples,
+            )),
+            _ => None,
+        };
+        let visible_fraction = VisibleFraction(visibility.map_or(1.0, |v| v.visible_fraction));
+
+        let ray_traced_transmission = RaycastTransmissionModel {
+            num_rays: settings.num_transmission_rays,
+        };
+        let medium_diffraction = self.medium_diffraction_model();
+        let air_absorption_model: &dyn AirAbsorptionModel = match &source.air_absorption_model {
+            Some(model) => model.as_ref(),
+            None => &self.direct_simulator.medium,
+        };
+
+        self.direct_simulator.simulate(
+            Some(&self.scene),
+            flags,
+            &closest,
+            &listener.coordinates,
+            source.distance_attenuation_model.as_ref(),
+            air_absorption_model,
+            match &source.diffraction_model {
+                Some(model) => model.as_ref(),
+                None => &medium_diffraction,
+            },
+            source.directivity_model.as_ref(),
+            match &source.occlusion_model {
+                Some(model) => model.as_ref(),
+                None => &visible_fraction,
+            },
+            match &source.transmission_model {
+                Some(model) => model.as_ref(),
+                None => &ray_traced_transmission,
+            },
+            &mut result.direct_sound_path,
+        );
+
+        if let ShapeAttenuation::Integral { density } = settings.shape_attenuation {
+            let samples = source.shape.sample_points(coordinates, num_samples);
+            let distances: Vec<f32> = samples
+                .iter()
+                .map(|sample| sample.distance(listener.coordinates.origin))
+                .collect();
+
+            // Sound from different parts of the shape is incoherent, so energies add up. Each
+            // sample stands for an equal part of the shape. Shapes without an extent count as a
+            // single point source.
+            let measure = source.shape.measure();
+            let power = if measure > 0.0 {
+                density * measure
+            } else {
+                1.0
+            };
+            let weight = power / distances.len() as f32;
+
+            let attenuations: Vec<f32> = distances
+                .iter()
+                .map(|&distance| {
+                    if flags.distance_attenuation {
+                        source.distance_attenuation_model.evaluate(distance)
+                    } else {
+                        1.0
+                    }
+                })
+                .collect();
+            let energy: f32 = attenuations.iter().map(|gain| gain.powi(2)).sum();
+
+            let path = &mut result.direct_sound_path;
+            if flags.distance_attenuation {
+                path.distance_attenuation = (energy * weight).sqrt();
+            }
+
+            // Air absorption stays a filter: the absorption of each sample, weighted by how much
+            // energy that sample contributes.
+            if flags.air_absorption && energy > 0.0 {
+                for band in 0..NUM_BANDS {
+                    let band_energy: f32 = attenuations
+                        .iter()
+                        .zip(&distances)
+                        .map(|(gain, &distance)| {
+                            (gain * air_absorption_model.evaluate(distance, band)).powi(2)
+                        })
+                        .sum();
+                    path.air_absorption[band] = (band_energy / energy).sqrt();
+                }
+            }
+        }
+
+        result.apparent_position = match visibility {
+            Some(visibility) => visibility.apparent_position,
+            None => {
+                let samples = source.shape.sample_points(coordinates, num_samples);
+                samples.iter().sum::<Vec3>() / samples.len() as f32
+            }
+        };
+    }
+}
+
+/// Occlusion of a shape, found with `SourceShape::visibility` before the direct sound path is
+/// simulated.
+struct VisibleFraction(f32);
+
+impl OcclusionModel for VisibleFraction {
+    fn evaluate(
+        &self,
+        _scene: &Scene,
+        _listener: &CoordinateSpace3f,
+        _source: &CoordinateSpace3f,
+    ) -> f32 {
+        self.0
+    }
+}
@@ -0,0 +1,236 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::scene::Scene;
+use crate::scene::coordinate_space::CoordinateSpace3f;
+use glam::{Vec2, Vec3};
+
+/// Extent of a sound source, for sources that are not a single point such as rivers, roads,
+/// crowds and waterfalls. Shapes are given in the local coordinate space of the source, so they
+/// move and rotate with it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SourceShape {
+    /// All sound comes from the origin of the source.
+    #[default]
+    Point,
+    /// A straight line from `start` to `end`, for example a road.
+    Segment { start: Vec3, end: Vec3 },
+    /// Connected line segments through the given points, for example a river.
+    Polyline(Vec<Vec3>),
+    /// A rectangle facing ahead, for example a waterfall. `half_extents` are along the right
+    /// and up axes of the source.
+    Rectangle { half_extents: Vec2 },
+    /// A box around the origin, for example a crowd.
+    Box { half_extents: Vec3 },
+}
+
+/// How distance attenuation and air absorption are evaluated for a source with a shape.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ShapeAttenuation {
+    /// Use the point of the shape that is closest to the listener.
+    #[default]
+    Nearest,
+    /// Sum the energy over the whole shape. A long road then sounds louder than a single car
+    /// at its nearest point would. Shapes without an extent sound like the source as a point.
+    Integral {
+        /// Sound power of each metre of a segment or polyline, square metre of a rectangle or
+        /// cubic metre of a box, relative to the power of the source as a point. With a
+        /// density of 1, a road sounds like a row of cars one metre apart. See
+        /// [`SourceShape::measure`].
+        density: f32,
+    },
+}
+
+/// The part of a shape the listener can see.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeVisibility {
+    /// Fraction of the samples that are not occluded, between 0 and 1.
+    pub visible_fraction: f32,
+    /// Center of the visible samples. This is where the sound appears to come from, and what
+    /// the binaural and panning effects should point at. If nothing is visible, the point
+    /// closest to the listener.
+    pub apparent_position: Vec3,
+}
+
+impl SourceShape {
+    /// Returns the point of the shape that is closest to `point`, in world space.
+    pub fn closest_point(&self, coordinates: &CoordinateSpace3f, point: Vec3) -> Vec3 {
+        let local = coordinates.direction_to_local(point - coordinates.origin);
+
+        let closest = match self {
+            SourceShape::Point => Vec3::ZERO,
+            SourceShape::Segment { start, end } => closest_point_on_segment(*start, *end, local),
+            SourceShape::Polyline(points) => points
+                .windows(2)
+                .map(|pair| closest_point_on_segment(pair[0], pair[1], local))
+                .min_by(|a, b| {
+                    a.distance_squared(local)
+                        .total_cmp(&b.distance_squared(local))
+                })
+                .or_else(|| points.first().copied())
+                .unwrap_or(Vec3::ZERO),
+            SourceShape::Rectangle { half_extents } => Vec3::new(
+                local.x.clamp(-half_extents.x, half_extents.x),
+                local.y.clamp(-half_extents.y, half_extents.y),
+                0.0,
+            ),
+            SourceShape::Box { half_extents } => local.clamp(-*half_extents, *half_extents),
+        };
+
+        coordinates.origin + coordinates.direction_to_world(closest)
+    }
+
+    /// Returns the length of a segment or polyline, the area of a rectangle, or the volume of a
+    /// box. A point has no extent and returns 0.
+    pub fn measure(&self) -> f32 {
+        match self {
+            SourceShape::Point => 0.0,
+            SourceShape::Segment { start, end } => start.distance(*end),
+            SourceShape::Polyline(points) => points
+                .windows(2)
+                .map(|pair| pair[0].distance(pair[1]))
+                .sum(),
+            SourceShape::Rectangle { half_extents } => 4.0 * half_extents.x * half_extents.y,
+            SourceShape::Box { half_extents } => {
+                8.0 * half_extents.x * half_extents.y * half_extents.z
+            }
+        }
+    }
+
+    /// Returns about `num_samples` points spread evenly across the shape, in world space. A
+    /// point shape always returns its origin.
+    pub fn sample_points(&self, coordinates: &CoordinateSpace3f, num_samples: usize) -> Vec<Vec3> {
+        let num_samples = num_samples.max(1);
+
+        let local_samples = match self {
+            SourceShape::Point => vec![Vec3::ZERO],
+            SourceShape::Segment { start, end } => (0..num_samples)
+                .map(|i| start.lerp(*end, (i as f32 + 0.5) / num_samples as f32))
+                .collect(),
+            SourceShape::Polyline(points) => sample_polyline(points, num_samples),
+            SourceShape::Rectangle { half_extents } => {
+                let per_side = (num_samples as f32).sqrt().ceil() as usize;
+                grid(per_side)
+                    .flat_map(|x| grid(per_side).map(move |y| Vec3::new(x, y, 0.0)))
+                    .map(|sample| sample * half_extents.extend(0.0))
+                    .collect()
+            }
+            SourceShape::Box { half_extents } => {
+                let per_side = (num_samples as f32).cbrt().ceil() as usize;
+                grid(per_side)
+                    .flat_map(|x| grid(per_side).map(move |y| (x, y)))
+                    .flat_map(|(x, y)| grid(per_side).map(move |z| Vec3::new(x, y, z)))
+                    .map(|sample| sample * *half_extents)
+                    .collect()
+            }
+        };
+
+        local_samples
+            .into_iter()
+            .map(|sample| coordinates.origin + coordinates.direction_to_world(sample))
+            .collect()
+    }
+
+    /// Casts rays from `listener` to samples across the shape to find out how much of it is
+    /// visible.
+    pub fn visibility(
+        &self,
+        scene: &Scene,
+        coordinates: &CoordinateSpace3f,
+        listener: Vec3,
+        num_samples: usize,
+    ) -> ShapeVisibility {
+        let samples = self.sample_points(coordinates, num_samples);
+
+        let mut num_visible = 0;
+        let mut visible_sum = Vec3::ZERO;
+
+        for sample in &samples {
+            if !scene.is_occluded(listener, *sample) {
+                num_visible += 1;
+                visible_sum += *sample;
+            }
+        }
+
+        let apparent_position = if num_visible > 0 {
+            visible_sum / num_visible as f32
+        } else {
+            self.closest_point(coordinates, listener)
+        };
+
+        ShapeVisibility {
+            visible_fraction: num_visible as f32 / samples.len() as f32,
+            apparent_position,
+        }
+    }
+}
+
+fn closest_point_on_segment(start: Vec3, end: Vec3, point: Vec3) -> Vec3 {
+    let direction = end - start;
+    let length_squared = direction.length_squared();
+    if length_squared <= f32::EPSILON {
+        return start;
+    }
+
+    let t = ((point - start).dot(direction) / length_squared).clamp(0.0, 1.0);
+    start + t * direction
+}
+
+/// Centers of `n` equal cells between -1 and 1.
+fn grid(n: usize) -> impl Iterator<Item = f32> + Clone {
+    (0..n).map(move |i| (i as f32 + 0.5) / n as f32 * 2.0 - 1.0)
+}
+
+/// Spreads samples over a polyline according to the length of each segment.
+fn sample_polyline(points: &[Vec3], num_samples: usize) -> Vec<Vec3> {
+    let total_length: f32 = points
+        .windows(2)
+        .map(|pair| pair[0].distance(pair[1]))
+        .sum();
+
+    if total_length <= f32::EPSILON {
+        return vec![points.first().copied().unwrap_or(Vec3::ZERO)];
+    }
+
+    let mut samples = Vec::with_capacity(num_samples);
+    let mut segments = points.windows(2);
+    let mut segment = segments.next().unwrap();
+    let mut segment_start = 0.0;
+
+    for i in 0..num_samples {
+        let position = (i as f32 + 0.5) / num_samples as f32 * total_length;
+
+        let mut segment_length = segment[0].distance(segment[1]);
+        while position > segment_start + segment_length {
+            let Some(next) = segments.next() else {
+                break;
+            };
+            segment_start += segment_length;
+            segment = next;
+            segment_length = segment[0].distance(segment[1]);
+        }
+
+        let t = if segment_length > 0.0 {
+            ((position - segment_start) / segment_length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        samples.push(segment[0].lerp(segment[1], t));
+    }
+
+    samples
+}
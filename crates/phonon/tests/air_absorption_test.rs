@@ -0,0 +1,54 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use phonon::models::air_absorption::{AirAbsorptionModel, IsoAirAbsorptionModel};
+
+const PRESSURE: f32 = IsoAirAbsorptionModel::STANDARD_PRESSURE;
+
+#[test]
+fn matches_iso_table() {
+    // Table 1 of ISO 9613-1, in dB per kilometer at 20 °C and 70% relative humidity.
+    let expected = [
+        (1000.0, 4.98),
+        (2000.0, 9.02),
+        (4000.0, 22.9),
+        (8000.0, 76.6),
+    ];
+
+    for (frequency, decibels_per_kilometer) in expected {
+        let attenuation = IsoAirAbsorptionModel::attenuation(frequency, 20.0, 70.0, PRESSURE);
+        let actual = attenuation * 1000.0;
+        assert!(
+            (actual - decibels_per_kilometer).abs() < 0.02 * decibels_per_kilometer,
+            "{frequency} Hz: expected {decibels_per_kilometer} dB/km, got {actual}"
+        );
+    }
+}
+
+#[test]
+fn dry_air_muffles_more() {
+    let dry = IsoAirAbsorptionModel::new(20.0, 10.0, PRESSURE);
+    let foggy = IsoAirAbsorptionModel::new(20.0, 100.0, PRESSURE);
+
+    assert_eq!(1.0, dry.evaluate(0.0, 2));
+    assert!(dry.evaluate(100.0, 2) < foggy.evaluate(100.0, 2));
+
+    // High frequencies are absorbed more than low ones, and more so further away.
+    let model = IsoAirAbsorptionModel::default();
+    assert!(model.evaluate(100.0, 2) < model.evaluate(100.0, 0));
+    assert!(model.evaluate(200.0, 2) < model.evaluate(100.0, 2));
+}
@@ -0,0 +1,185 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+mod common;
+
+use common::{NoAirAbsorption, box_mesh, medium_without_absorption, scene_with};
+use glam::Vec3;
+use phonon::dsp::energy_field::EnergyField;
+use phonon::dsp::reverb_estimator::{Reverb, ReverbEstimator, ReverbFormula, RoomProperties};
+use phonon::probes::baking::{BakedReflectionData, ReflectionBakeSettings, ReflectionBaker};
+use phonon::probes::{Probe, ProbeBatch};
+use phonon::scene::Scene;
+use phonon::scene::material::Material;
+use std::io;
+
+const MATERIAL: Material = Material {
+    absorption: [0.1, 0.2, 0.3],
+    scattering: 0.5,
+    transmission: [0.0, 0.0, 0.0],
+};
+
+fn bake(store_energy_fields: bool, seed: u64) -> (Scene, BakedReflectionData) {
+    let scene = scene_with(box_mesh(Vec3::new(5.0, 2.0, 4.0), true, MATERIAL));
+
+    let mut probe_batch = ProbeBatch::new();
+    probe_batch.add_probe(Probe::new(Vec3::new(-2.0, 0.1, 0.3), 4.0));
+    probe_batch.add_probe(Probe::new(Vec3::new(2.0, 0.1, -0.3), 4.0));
+
+    let settings = ReflectionBakeSettings {
+        num_rays: 2048,
+        num_bounces: 64,
+        duration: 1.5,
+        store_energy_fields,
+        seed,
+        ..Default::default()
+    };
+
+    let baked_data = ReflectionBaker::bake(&scene, &probe_batch, &settings, &NoAirAbsorption);
+    (scene, baked_data)
+}
+
+#[test]
+fn baked_reverb_matches_eyring() {
+    let (scene, baked_data) = bake(false, 0);
+
+    let room = RoomProperties {
+        volume: 10.0 * 4.0 * 8.0,
+        surface_area: 2.0 * (10.0 * 4.0 + 10.0 * 8.0 + 4.0 * 8.0),
+        absorption: MATERIAL.absorption,
+    };
+    let mut expected = Reverb::default();
+    ReverbEstimator::estimate_from_room(
+        &room,
+        ReverbFormula::Eyring,
+        &medium_without_absorption(),
+        &mut expected,
+    );
+
+    for data in baked_data.probe_data() {
+        assert!(data.energy_field.is_none());
+        for (baked, expected) in data.reverb.reverb_times.iter().zip(expected.reverb_times) {
+            assert!(
+                (baked - expected).abs() < 0.3 * expected,
+                "baked {baked}, expected {expected}"
+            );
+        }
+    }
+
+    // Halfway between the probes the result is the average of both.
+    let reverb = baked_data
+        .reverb_at(Some(&scene), Vec3::new(0.0, 0.1, 0.0))
+        .unwrap();
+    let probe_data = baked_data.probe_data();
+    for band in 0..3 {
+        let average = (probe_data[0].reverb.reverb_times[band]
+            + probe_data[1].reverb.reverb_times[band])
+            / 2.0;
+        assert!((reverb.reverb_times[band] - average).abs() < 1e-4);
+    }
+
+    // Outside the box no probe can be seen.
+    assert!(
+        baked_data
+            .reverb_at(Some(&scene), Vec3::new(0.0, 3.0, 0.0))
+            .is_none()
+    );
+}
+
+#[test]
+fn serialization_round_trip() {
+    let (_, baked_data) = bake(true, 0);
+
+    let mut bytes = Vec::new();
+    baked_data.write(&mut bytes).unwrap();
+
+    let read_data = BakedReflectionData::read(&mut bytes.as_slice()).unwrap();
+    assert_eq!(read_data.probe_data(), baked_data.probe_data());
+    assert_eq!(
+        read_data.probe_batch().probes(),
+        baked_data.probe_batch().probes()
+    );
+
+    let mut energy_field = EnergyField::new(1.5, 0);
+    assert!(read_data.energy_field_at(None, Vec3::new(-2.0, 0.1, 0.3), &mut energy_field));
+    assert!(energy_field.data.sum() > 0.0);
+
+    // Corrupting the version makes the data unreadable.
+    bytes[4] = 0xff;
+    assert!(BakedReflectionData::read(&mut bytes.as_slice()).is_err());
+}
+
+#[test]
+fn oversized_files_are_rejected() {
+    let (_, baked_data) = bake(true, 0);
+
+    let mut bytes = Vec::new();
+    baked_data.write(&mut bytes).unwrap();
+
+    let read_error = |bytes: &[u8]| {
+        let mut reader = bytes;
+        BakedReflectionData::read(&mut reader).unwrap_err().kind()
+    };
+
+    // The number of probes follows the magic and the version.
+    let mut too_many_probes = bytes.clone();
+    too_many_probes[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(read_error(&too_many_probes), io::ErrorKind::InvalidData);
+
+    // The size of the first energy field follows the position, radius, reverb times and energy
+    // field flag of the first probe.
+    let mut huge_energy_field = bytes;
+    huge_energy_field[41..49].fill(0xff);
+    assert_eq!(read_error(&huge_energy_field), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn truncated_files_are_rejected() {
+    let header = |num_probes: u32| {
+        let mut bytes = b"PHRF".to_vec();
+        bytes.extend(BakedReflectionData::VERSION.to_le_bytes());
+        bytes.extend(num_probes.to_le_bytes());
+        bytes
+    };
+    let read_error = |bytes: &[u8]| {
+        let mut reader = bytes;
+        BakedReflectionData::read(&mut reader).unwrap_err().kind()
+    };
+
+    // The header claims the largest number of probes, but the file ends right after it.
+    assert_eq!(read_error(&header(1 << 20)), io::ErrorKind::UnexpectedEof);
+
+    // A single probe that claims the largest energy field, without any of its values.
+    let mut bytes = header(1);
+    for value in [0.0f32; 7] {
+        bytes.extend(value.to_le_bytes());
+    }
+    bytes.push(1);
+    bytes.extend(1u32.to_le_bytes());
+    bytes.extend(((1u32 << 24) / 3).to_le_bytes());
+    assert_eq!(read_error(&bytes), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn baking_is_deterministic() {
+    let (_, first) = bake(true, 7);
+    let (_, second) = bake(true, 7);
+    assert_eq!(first.probe_data(), second.probe_data());
+
+    let (_, other_seed) = bake(true, 8);
+    assert_ne!(first.probe_data(), other_seed.probe_data());
+}
@@ -0,0 +1,116 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Scenes and models shared by the integration tests. Every test file compiles this module on
+//! its own and only uses part of it.
+#![allow(dead_code)]
+
+use glam::Vec3;
+use phonon::models::air_absorption::AirAbsorptionModel;
+use phonon::models::propagation_medium::PropagationMedium;
+use phonon::scene::Scene;
+use phonon::scene::material::Material;
+use phonon::scene::static_mesh::StaticMesh;
+use std::sync::Arc;
+
+/// A quad with the given corners, in counter-clockwise order.
+pub fn quad_mesh(corners: [Vec3; 4]) -> StaticMesh {
+    StaticMesh::new_static_mesh(
+        corners.to_vec(),
+        vec![[0, 1, 2], [0, 2, 3]],
+        vec![0, 0],
+        vec![Material::default()],
+    )
+}
+
+/// A wall in the plane z = 0, 2 meters high and 20 meters wide, standing on nothing.
+pub fn wall_mesh() -> StaticMesh {
+    quad_mesh([
+        Vec3::new(-10.0, 0.0, 0.0),
+        Vec3::new(10.0, 0.0, 0.0),
+        Vec3::new(10.0, 2.0, 0.0),
+        Vec3::new(-10.0, 2.0, 0.0),
+    ])
+}
+
+/// A committed scene that only contains `mesh`.
+pub fn scene_with(mesh: StaticMesh) -> Scene {
+    let mut scene = Scene::new();
+    scene.add_static_mesh(Arc::new(mesh));
+    scene.commit();
+    scene
+}
+
+/// A committed scene with the `wall_mesh`.
+pub fn wall_scene() -> Scene {
+    scene_with(wall_mesh())
+}
+
+/// A box with the given half extents, centered at the origin, with `material` on every side.
+/// Without a ceiling the box is open at the top.
+pub fn box_mesh(half_extents: Vec3, with_ceiling: bool, material: Material) -> StaticMesh {
+    let Vec3 { x, y, z } = half_extents;
+
+    let vertices = vec![
+        Vec3::new(-x, -y, -z),
+        Vec3::new(x, -y, -z),
+        Vec3::new(x, y, -z),
+        Vec3::new(-x, y, -z),
+        Vec3::new(-x, -y, z),
+        Vec3::new(x, -y, z),
+        Vec3::new(x, y, z),
+        Vec3::new(-x, y, z),
+    ];
+
+    let mut triangles = vec![
+        [0, 1, 2],
+        [0, 2, 3],
+        [4, 6, 5],
+        [4, 7, 6],
+        [0, 4, 5],
+        [0, 5, 1],
+        [1, 5, 6],
+        [1, 6, 2],
+        [0, 3, 7],
+        [0, 7, 4],
+    ];
+
+    if with_ceiling {
+        triangles.extend([[3, 2, 6], [3, 6, 7]]);
+    }
+
+    let material_indices = vec![0; triangles.len()];
+
+    StaticMesh::new_static_mesh(vertices, triangles, material_indices, vec![material])
+}
+
+/// Lets sound through at every distance.
+pub struct NoAirAbsorption;
+
+impl AirAbsorptionModel for NoAirAbsorption {
+    fn evaluate(&self, _distance: f32, _band: usize) -> f32 {
+        1.0
+    }
+}
+
+/// The default medium, without any absorption.
+pub fn medium_without_absorption() -> PropagationMedium {
+    PropagationMedium {
+        absorption: Default::default(),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,88 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+mod common;
+
+use common::wall_scene;
+use glam::Vec3;
+use phonon::effects::direct::DirectApplyFlags;
+use phonon::models::air_absorption::DefaultAirAbsorptionModel;
+use phonon::models::diffraction::{DefaultDiffractionModel, DiffractionModel};
+use phonon::models::directivity::Directivity;
+use phonon::models::distance_attenuation::DefaultDistanceAttenuationModel;
+use phonon::models::transmission::RaycastTransmissionModel;
+use phonon::scene::Scene;
+use phonon::scene::coordinate_space::CoordinateSpace3f;
+use phonon::simulators::direct::{DirectSimulator, DirectSoundPath, OcclusionType};
+
+fn simulate(scene: &Scene, source: Vec3, listener: Vec3) -> DirectSoundPath {
+    let simulator = DirectSimulator::new(1);
+    let mut direct_sound_path = DirectSoundPath::default();
+
+    simulator.simulate(
+        Some(scene),
+        DirectApplyFlags::all(),
+        &CoordinateSpace3f::from_origin(source),
+        &CoordinateSpace3f::from_origin(listener),
+        &DefaultDistanceAttenuationModel::default(),
+        &DefaultAirAbsorptionModel::default(),
+        &DefaultDiffractionModel::default(),
+        &Directivity::default(),
+        &simulator.occlusion_model(OcclusionType::Raycast, 0.0, 0.0, 0),
+        &RaycastTransmissionModel { num_rays: 0 },
+        &mut direct_sound_path,
+    );
+
+    direct_sound_path
+}
+
+#[test]
+fn maekawa_attenuation() {
+    let model = DefaultDiffractionModel::default();
+
+    // Grazing incidence is about 4.8 dB down for every band.
+    for band in 0..3 {
+        assert!((model.evaluate(0.0, band) - 0.577).abs() < 1e-3);
+    }
+
+    // Higher frequencies are attenuated more, up to the limit.
+    let gains: Vec<f32> = (0..3).map(|band| model.evaluate(0.2, band)).collect();
+    assert!(gains[0] > gains[1] && gains[1] > gains[2]);
+    assert!((model.evaluate(100.0, 2) - 0.1).abs() < 1e-4);
+}
+
+#[test]
+fn sound_bends_over_wall() {
+    let scene = wall_scene();
+
+    // Source and listener on opposite sides, just below the top of the wall.
+    let shallow = simulate(&scene, Vec3::new(0.3, 1.8, -3.0), Vec3::new(0.0, 1.8, 3.0));
+    assert_eq!(shallow.occlusion, 0.0);
+    assert!(shallow.diffraction[0] > shallow.diffraction[1]);
+    assert!(shallow.diffraction[1] > shallow.diffraction[2]);
+    assert!(shallow.diffraction[2] > 0.0);
+
+    // Deeper in the shadow of the wall, more sound is lost.
+    let deep = simulate(&scene, Vec3::new(0.3, 0.5, -1.0), Vec3::new(0.0, 0.5, 1.0));
+    assert_eq!(deep.occlusion, 0.0);
+    assert!(deep.diffraction[0] < shallow.diffraction[0]);
+
+    // Without occlusion there is nothing to diffract.
+    let visible = simulate(&scene, Vec3::new(0.3, 3.0, -3.0), Vec3::new(0.0, 3.0, 3.0));
+    assert_eq!(visible.occlusion, 1.0);
+    assert_eq!(visible.diffraction, [0.0; 3]);
+}
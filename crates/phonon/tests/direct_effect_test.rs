@@ -0,0 +1,132 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use phonon::dsp::audio_buffer::AudioSettings;
+use phonon::effects::direct::{
+    DirectApplyFlags, DirectEffect, DirectEffectParameters, DirectSmoothing, SmoothingTimes,
+};
+use phonon::simulators::direct::DirectSoundPath;
+
+const SAMPLING_RATE: u32 = 48_000;
+const FRAME_SIZE: usize = 480;
+
+/// Runs one frame of a constant signal through the effect and returns the last output sample.
+fn process(effect: &mut DirectEffect, parameters: DirectEffectParameters) -> f32 {
+    let input = vec![1.0; FRAME_SIZE];
+    let mut output = vec![0.0; FRAME_SIZE];
+
+    effect.apply(parameters, &[&input], &mut [&mut output]);
+    output[FRAME_SIZE - 1]
+}
+
+#[test]
+fn occlusion_release() {
+    let mut effect = DirectEffect::new(AudioSettings::new(SAMPLING_RATE, FRAME_SIZE));
+
+    let mut parameters = DirectEffectParameters {
+        flags: DirectApplyFlags {
+            occlusion: true,
+            ..DirectApplyFlags::none()
+        },
+        smoothing: DirectSmoothing {
+            occlusion: SmoothingTimes::new(0.0, 0.1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    assert!((process(&mut effect, parameters) - 1.0).abs() < 1e-4);
+
+    // The source suddenly becomes occluded. Without smoothing, the gain effect alone would drop
+    // to 0.75 within one frame.
+    parameters.direct_sound_path = DirectSoundPath {
+        occlusion: 0.0,
+        ..parameters.direct_sound_path
+    };
+    assert!(process(&mut effect, parameters) > 0.95);
+
+    // After a second it is practically silent.
+    for _ in 0..100 {
+        process(&mut effect, parameters);
+    }
+    assert!(process(&mut effect, parameters) < 1e-3);
+
+    // With a zero attack time the source is only limited by the gain interpolation.
+    parameters.direct_sound_path.occlusion = 1.0;
+    for _ in 0..40 {
+        process(&mut effect, parameters);
+    }
+    assert!((process(&mut effect, parameters) - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn propagation_delay() {
+    let mut effect = DirectEffect::new(AudioSettings::new(SAMPLING_RATE, FRAME_SIZE));
+
+    let mut parameters = DirectEffectParameters {
+        flags: DirectApplyFlags {
+            delay: true,
+            ..DirectApplyFlags::none()
+        },
+        ..Default::default()
+    };
+    parameters.direct_sound_path.delay = 0.015;
+
+    let mut input = vec![0.0; FRAME_SIZE];
+    input[0] = 1.0;
+    let mut output = vec![0.0; FRAME_SIZE];
+
+    // 15 ms is 720 samples, so the impulse arrives halfway through the second frame.
+    effect.apply(parameters, &[&input], &mut [&mut output]);
+    assert!(output.iter().all(|&sample| sample == 0.0));
+
+    input[0] = 0.0;
+    effect.apply(parameters, &[&input], &mut [&mut output]);
+    assert!((output[240] - 1.0).abs() < 1e-6);
+    assert!((output.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+
+    // Halving the Doppler scale halves the delay.
+    let mut effect = DirectEffect::new(AudioSettings::new(SAMPLING_RATE, FRAME_SIZE));
+    parameters.doppler_scale = 0.5;
+
+    input[0] = 1.0;
+    effect.apply(parameters, &[&input], &mut [&mut output]);
+    assert!((output[360] - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn directivity_is_frequency_dependent() {
+    let mut effect = DirectEffect::new(AudioSettings::new(SAMPLING_RATE, FRAME_SIZE));
+
+    // The source faces away from the listener: the high frequencies are mostly gone, the low
+    // frequencies are not.
+    let mut parameters = DirectEffectParameters {
+        flags: DirectApplyFlags {
+            directivity: true,
+            ..DirectApplyFlags::none()
+        },
+        ..Default::default()
+    };
+    parameters.direct_sound_path.directivity = [1.0, 0.2, 0.05];
+
+    for _ in 0..20 {
+        process(&mut effect, parameters);
+    }
+
+    // A constant signal only has low frequencies, so it passes almost unchanged.
+    assert!((process(&mut effect, parameters) - 1.0).abs() < 0.05);
+}
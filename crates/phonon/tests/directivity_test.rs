@@ -0,0 +1,157 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use glam::Vec3;
+use phonon::models::directivity::{
+    ConeDirectivity, Directivity, DirectivityModel, MultibandDirectivity, TabulatedDirectivity,
+};
+use phonon::scene::coordinate_space::CoordinateSpace3f;
+use phonon::simulators::simulator::{Simulator, Source};
+use std::sync::{Arc, Mutex};
+
+const AHEAD: Vec3 = Vec3::new(0.0, 0.0, -1.0);
+const RIGHT: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+const BEHIND: Vec3 = Vec3::new(0.0, 0.0, 1.0);
+const UP: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+
+#[test]
+fn cone() {
+    let cone = ConeDirectivity::new(90.0, 270.0, 0.25);
+
+    assert_eq!(1.0, cone.evaluate(AHEAD));
+    assert_eq!(1.0, cone.evaluate(Vec3::new(1.0, 0.0, -1.0).normalize()));
+    assert_eq!(0.25, cone.evaluate(BEHIND));
+
+    // Halfway between the inner (45 degrees) and outer (135 degrees) edges.
+    assert!((cone.evaluate(RIGHT) - 0.625).abs() < 1e-5);
+}
+
+#[test]
+fn balloon_from_file() {
+    // Four azimuths (ahead, right, behind, left) and three elevations (below, level, above).
+    let file = "\
+        # azimuths elevations
+        4 3
+        # below
+        -6 -6 -6
+        -6 -6 -6
+        -6 -6 -6
+        -6 -6 -6
+        # level: louder and brighter ahead
+        0 0 0
+        -6 -12 -20
+        -6 -20 -40
+        -6 -12 -20
+        # above
+        -6 -6 -6
+        -6 -6 -6
+        -6 -6 -6
+        -6 -6 -6
+    ";
+
+    let balloon = TabulatedDirectivity::read(&mut file.as_bytes()).unwrap();
+
+    assert!((balloon.evaluate_band(AHEAD, 2) - 1.0).abs() < 1e-5);
+    assert!((balloon.evaluate_band(BEHIND, 2) - 0.01).abs() < 1e-5);
+    assert!((balloon.evaluate_band(UP, 0) - 0.501).abs() < 1e-3);
+
+    // Between ahead and right the gains are interpolated.
+    let between = Vec3::new(1.0, 0.0, -1.0).normalize();
+    let expected = (1.0 + 10.0f32.powf(-12.0 / 20.0)) / 2.0;
+    assert!((balloon.evaluate_band(between, 1) - expected).abs() < 1e-5);
+
+    // The broadband gain is the average over the bands.
+    let behind = (0.501 + 0.1 + 0.01) / 3.0;
+    assert!((balloon.evaluate(BEHIND) - behind).abs() < 1e-3);
+
+    // A source turned towards the listener sounds like its front.
+    let source = CoordinateSpace3f::from_vectors(RIGHT, UP, Vec3::ZERO);
+    assert!((balloon.evaluate_at(Vec3::new(5.0, 0.0, 0.0), &source) - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn invalid_balloon() {
+    let too_short = "2 2\n0 0 0\n0 0 0\n0 0 0\n";
+    assert!(TabulatedDirectivity::read(&mut too_short.as_bytes()).is_err());
+
+    let not_a_number = "1 2\n0 0 0\n0 zero 0\n";
+    assert!(TabulatedDirectivity::read(&mut not_a_number.as_bytes()).is_err());
+
+    // Sizes that would overflow or exhaust memory are rejected before anything is allocated.
+    let huge = format!("{} {}", usize::MAX, 2);
+    let error = TabulatedDirectivity::read(&mut huge.as_bytes()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    let large = "100000 100000";
+    let error = TabulatedDirectivity::read(&mut large.as_bytes()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn closure() {
+    let front_only = |direction: Vec3| if direction.z < 0.0 { 1.0 } else { 0.0 };
+
+    assert_eq!(1.0, front_only.evaluate(AHEAD));
+    assert_eq!(0.0, front_only.evaluate(BEHIND));
+}
+
+#[test]
+fn talker_turning_away_sounds_duller() {
+    let talker = MultibandDirectivity::new([
+        Directivity {
+            dipole_weight: 0.2,
+            dipole_power: 1.0,
+        },
+        Directivity {
+            dipole_weight: 0.4,
+            dipole_power: 1.0,
+        },
+        Directivity {
+            dipole_weight: 0.45,
+            dipole_power: 3.0,
+        },
+    ]);
+
+    let mut simulator = Simulator::new(1);
+    simulator.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::new(0.0, 0.0, 5.0));
+
+    // The source faces away from the listener, towards -z.
+    let mut source = Source::new(
+        CoordinateSpace3f::from_vectors(AHEAD, UP, Vec3::ZERO),
+        Default::default(),
+    );
+    source.directivity_model = Box::new(talker);
+    let source = Arc::new(Mutex::new(source));
+    simulator.add_source(source.clone());
+    simulator.commit();
+    simulator.run_direct();
+
+    let directivity = source.lock().unwrap().listener_results[0]
+        .direct_sound_path
+        .directivity;
+    assert!((directivity[0] - 0.6).abs() < 1e-5);
+    assert!(directivity[0] > directivity[1] && directivity[1] > directivity[2]);
+    assert!(directivity[2] < 0.01);
+
+    // Models without bands give the same gain in every band.
+    let source = CoordinateSpace3f::from_vectors(AHEAD, UP, Vec3::ZERO);
+    let cone = ConeDirectivity::new(90.0, 270.0, 0.25);
+    assert_eq!(
+        [0.25; 3],
+        cone.evaluate_bands_at(Vec3::new(0.0, 0.0, 5.0), &source)
+    );
+}
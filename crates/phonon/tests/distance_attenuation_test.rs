@@ -0,0 +1,120 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use phonon::models::distance_attenuation::{
+    CurveDistanceModel, CurveInterpolation, CurvePoint, DistanceAttenuationModel,
+    ExponentialDistanceModel, InverseDistanceModel, LinearDistanceModel,
+};
+
+fn assert_close(expected: f32, actual: f32) {
+    assert!(
+        (expected - actual).abs() < 1e-5,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn inverse() {
+    let model = InverseDistanceModel {
+        min_distance: 2.0,
+        max_distance: 20.0,
+        rolloff: 1.0,
+    };
+
+    assert_close(1.0, model.evaluate(0.5));
+    assert_close(0.5, model.evaluate(4.0));
+    assert_close(0.1, model.evaluate(20.0));
+    assert_close(0.1, model.evaluate(100.0));
+
+    // A higher rolloff fades faster.
+    let steep = InverseDistanceModel {
+        rolloff: 3.0,
+        ..model
+    };
+    assert_close(0.25, steep.evaluate(4.0));
+}
+
+#[test]
+fn linear() {
+    let model = LinearDistanceModel {
+        min_distance: 10.0,
+        max_distance: 30.0,
+    };
+
+    assert_close(1.0, model.evaluate(5.0));
+    assert_close(0.5, model.evaluate(20.0));
+    assert_close(0.0, model.evaluate(30.0));
+    assert_close(0.0, model.evaluate(1000.0));
+}
+
+#[test]
+fn exponential() {
+    let model = ExponentialDistanceModel {
+        min_distance: 1.0,
+        rolloff: 2.0,
+    };
+
+    assert_close(1.0, model.evaluate(0.1));
+    assert_close(0.25, model.evaluate(2.0));
+    assert_close(0.01, model.evaluate(10.0));
+
+    // A zero min_distance does not produce NaN.
+    let model = ExponentialDistanceModel {
+        min_distance: 0.0,
+        rolloff: 1.0,
+    };
+    assert_close(1.0, model.evaluate(0.0));
+    assert!(model.evaluate(10.0).is_finite());
+}
+
+#[test]
+fn curve() {
+    let points = vec![
+        CurvePoint::new(10.0, 0.2),
+        CurvePoint::new(0.0, 1.0),
+        CurvePoint::new(5.0, 0.8),
+        CurvePoint::new(20.0, 0.0),
+    ];
+
+    let linear = CurveDistanceModel::new(points.clone(), CurveInterpolation::Linear);
+    assert!(
+        linear
+            .points()
+            .windows(2)
+            .all(|pair| pair[0].distance <= pair[1].distance)
+    );
+    assert_close(1.0, linear.evaluate(-1.0));
+    assert_close(0.9, linear.evaluate(2.5));
+    assert_close(0.5, linear.evaluate(7.5));
+    assert_close(0.0, linear.evaluate(50.0));
+
+    // The spline passes through the points and keeps decreasing in between.
+    let spline = CurveDistanceModel::new(points, CurveInterpolation::Spline);
+    assert_close(0.8, spline.evaluate(5.0));
+    assert_close(0.2, spline.evaluate(10.0));
+
+    let mut previous = spline.evaluate(0.0);
+    for step in 1..=200 {
+        let gain = spline.evaluate(step as f32 * 0.1);
+        assert!(gain <= previous + 1e-6);
+        assert!((0.0..=1.0).contains(&gain));
+        previous = gain;
+    }
+
+    // An empty curve does not attenuate.
+    assert_close(1.0, CurveDistanceModel::default().evaluate(10.0));
+}
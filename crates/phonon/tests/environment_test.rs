@@ -0,0 +1,85 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+mod common;
+
+use common::box_mesh;
+use glam::Vec3;
+use phonon::scene::Scene;
+use phonon::scene::coordinate_space::CoordinateSpace3f;
+use phonon::scene::material::Material;
+use phonon::scene::static_mesh::StaticMesh;
+use phonon::simulators::environment::{EnvironmentSimulator, EnvironmentSummary};
+use std::sync::Arc;
+
+const MATERIAL: Material = Material {
+    absorption: [0.1, 0.2, 0.3],
+    scattering: 0.05,
+    transmission: [0.0, 0.0, 0.0],
+};
+
+fn summarize(mesh: StaticMesh) -> EnvironmentSummary {
+    let mut scene = Scene::new();
+    scene.add_static_mesh(Arc::new(mesh));
+    scene.commit();
+
+    let simulator = EnvironmentSimulator::new(2048);
+    let mut summary = EnvironmentSummary::default();
+    simulator.simulate(
+        &scene,
+        &CoordinateSpace3f::from_origin(Vec3::new(0.5, -1.0, 0.3)),
+        100.0,
+        &mut summary,
+    );
+
+    summary
+}
+
+#[test]
+fn closed_room() {
+    let half_extents = Vec3::new(5.0, 2.0, 4.0);
+    let summary = summarize(box_mesh(half_extents, true, MATERIAL));
+
+    let volume = 8.0 * half_extents.x * half_extents.y * half_extents.z;
+    let surface_area = 8.0
+        * (half_extents.x * half_extents.y
+            + half_extents.y * half_extents.z
+            + half_extents.x * half_extents.z);
+
+    assert_eq!(summary.outdoorness, 0.0);
+    assert!((summary.room_volume / volume - 1.0).abs() < 0.05);
+    assert!((summary.surface_area / surface_area - 1.0).abs() < 0.1);
+    assert!((summary.mean_free_path - 4.0 * volume / surface_area).abs() < 0.5);
+    assert!((summary.absorption[1] - 0.2).abs() < 1e-4);
+}
+
+#[test]
+fn room_without_ceiling() {
+    let half_extents = Vec3::new(5.0, 2.0, 4.0);
+    let closed = summarize(box_mesh(half_extents, true, MATERIAL));
+    let summary = summarize(box_mesh(half_extents, false, MATERIAL));
+
+    assert!(summary.outdoorness > 0.1);
+    assert!(summary.outdoorness < 0.5);
+
+    // The part of the room that is open to the sky is not counted, instead of being filled in
+    // from the walls that were hit.
+    let enclosed_fraction = 1.0 - summary.outdoorness;
+    assert!(summary.room_volume < closed.room_volume * enclosed_fraction);
+    assert!(summary.surface_area < closed.surface_area);
+    assert!(summary.room_volume > 0.0);
+}
@@ -0,0 +1,79 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+mod common;
+
+use common::wall_mesh;
+use glam::Vec3;
+use phonon::scene::coordinate_space::CoordinateSpace3f;
+use phonon::simulators::hearing::HearingQuery;
+use phonon::simulators::simulator::{Simulator, Source, SourceSettings};
+use std::sync::Arc;
+
+#[test]
+fn npcs_hear_the_player() {
+    let mut simulator = Simulator::new(16);
+    simulator.scene.add_static_mesh(Arc::new(wall_mesh()));
+    simulator.commit();
+
+    // The player is not added to the simulator, as only NPCs listen to it.
+    let player = Source::new(
+        CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, 2.0)),
+        SourceSettings::default(),
+    );
+    let footsteps = |position: Vec3| HearingQuery {
+        source: &player,
+        position,
+        level: 50.0,
+    };
+
+    let queries = [
+        // Right next to the player.
+        footsteps(Vec3::new(0.5, 1.0, 2.0)),
+        // Ten meters away, in the open.
+        footsteps(Vec3::new(0.0, 1.0, 12.0)),
+        // Ten meters away, behind the wall.
+        footsteps(Vec3::new(0.0, 1.0, -8.0)),
+    ];
+    let results = simulator.hear_all(&queries);
+
+    let [close, open, behind_wall] = results.as_slice() else {
+        panic!("expected a result per query");
+    };
+
+    // Within the minimum distance of the attenuation model, the sound is heard at its level
+    // apart from a little air absorption.
+    assert!((close.max_level() - 50.0).abs() < 0.1);
+
+    // Distance lowers the level, and the wall lowers it further. Sound still bends over the
+    // top of the wall, mostly at low frequencies.
+    assert!(open.max_level() < close.max_level() - 15.0);
+    assert!(behind_wall.max_level() < open.max_level() - 6.0);
+    assert!(behind_wall.levels[2] < open.levels[2] - 15.0);
+
+    // High frequencies are absorbed more by the air.
+    assert!(open.levels[2] < open.levels[0]);
+    assert!(open.delay > close.delay);
+
+    // A batch gives the same answers as asking one at a time.
+    for (query, result) in queries.iter().zip(&results) {
+        assert_eq!(simulator.hear(query), *result);
+    }
+
+    // Asking does not simulate the source for any listener.
+    assert!(player.listener_results.is_empty());
+}
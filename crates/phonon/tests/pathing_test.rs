@@ -0,0 +1,103 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+mod common;
+
+use common::{quad_mesh, scene_with};
+use glam::Vec3;
+use phonon::models::air_absorption::DefaultAirAbsorptionModel;
+use phonon::models::deviation::DefaultDeviationModel;
+use phonon::models::distance_attenuation::DefaultDistanceAttenuationModel;
+use phonon::scene::Scene;
+use phonon::scene::coordinate_space::CoordinateSpace3f;
+use phonon::simulators::pathing::{PathingSimulator, PathingSoundPath, VisibilityGraph};
+
+/// A wall in the z = 0 plane, which ends at x = 2. Sound can go around it on the +x side.
+fn wall_scene() -> Scene {
+    scene_with(quad_mesh([
+        Vec3::new(-10.0, -10.0, 0.0),
+        Vec3::new(2.0, -10.0, 0.0),
+        Vec3::new(2.0, 10.0, 0.0),
+        Vec3::new(-10.0, 10.0, 0.0),
+    ]))
+}
+
+fn simulate(scene: &Scene, simulator: &PathingSimulator, source: Vec3) -> PathingSoundPath {
+    let mut sound_path = PathingSoundPath::default();
+
+    simulator.simulate(
+        scene,
+        &CoordinateSpace3f::from_origin(source),
+        &CoordinateSpace3f::from_origin(Vec3::new(0.0, 0.0, -2.0)),
+        &DefaultDistanceAttenuationModel::default(),
+        &DefaultAirAbsorptionModel::default(),
+        &DefaultDeviationModel::default(),
+        &mut sound_path,
+    );
+
+    sound_path
+}
+
+#[test]
+fn path_around_wall() {
+    let scene = wall_scene();
+
+    let nodes = vec![
+        Vec3::new(4.0, 0.0, 0.0),
+        Vec3::new(-12.0, 0.0, -1.0),
+        Vec3::new(4.0, 0.0, 6.0),
+    ];
+    let graph = VisibilityGraph::new(&scene, nodes, 50.0);
+    assert_eq!(graph.num_edges(), 2);
+
+    let simulator = PathingSimulator::new(graph, 50.0);
+
+    let sound_path = simulate(&scene, &simulator, Vec3::new(0.0, 0.0, 2.0));
+
+    assert!(sound_path.path_found);
+    assert!((sound_path.distance - 2.0 * 20.0f32.sqrt()).abs() < 1e-4);
+    assert!(
+        sound_path
+            .direction
+            .abs_diff_eq(Vec3::new(4.0, 0.0, 2.0).normalize(), 1e-5)
+    );
+
+    // Bending around the corner affects high frequencies the most.
+    assert!(sound_path.eq_coefficients[0] > sound_path.eq_coefficients[2]);
+}
+
+#[test]
+fn direct_path() {
+    let scene = wall_scene();
+    let simulator = PathingSimulator::new(VisibilityGraph::new(&scene, Vec::new(), 50.0), 50.0);
+
+    let sound_path = simulate(&scene, &simulator, Vec3::new(0.0, 0.0, -5.0));
+
+    assert!(sound_path.path_found);
+    assert!((sound_path.distance - 3.0).abs() < 1e-5);
+    assert!(sound_path.direction.abs_diff_eq(Vec3::NEG_Z, 1e-5));
+}
+
+#[test]
+fn no_path() {
+    let scene = wall_scene();
+    let simulator = PathingSimulator::new(VisibilityGraph::new(&scene, Vec::new(), 50.0), 50.0);
+
+    let sound_path = simulate(&scene, &simulator, Vec3::new(0.0, 0.0, 2.0));
+
+    assert!(!sound_path.path_found);
+}
@@ -0,0 +1,128 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use glam::{Mat4, Vec3};
+use phonon::probes::generator::{ProbeGenerationType, ProbeGenerator};
+use phonon::probes::{Probe, ProbeBatch};
+use phonon::scene::Scene;
+use phonon::scene::material::Material;
+use phonon::scene::static_mesh::StaticMesh;
+use std::sync::Arc;
+
+/// A 10 by 10 meter floor at y = 0.
+fn floor_scene() -> Scene {
+    let vertices = vec![
+        Vec3::new(-5.0, 0.0, -5.0),
+        Vec3::new(5.0, 0.0, -5.0),
+        Vec3::new(5.0, 0.0, 5.0),
+        Vec3::new(-5.0, 0.0, 5.0),
+    ];
+
+    let static_mesh = StaticMesh::new_static_mesh(
+        vertices,
+        vec![[0, 2, 1], [0, 3, 2]],
+        vec![0, 0],
+        vec![Material::default()],
+    );
+
+    let mut scene = Scene::new();
+    scene.add_static_mesh(Arc::new(static_mesh));
+    scene.commit();
+    scene
+}
+
+#[test]
+fn uniform_floor_generation() {
+    let scene = floor_scene();
+    let volume = Mat4::from_scale_rotation_translation(
+        Vec3::new(9.0, 4.0, 9.0),
+        Default::default(),
+        Vec3::new(0.0, 1.0, 0.0),
+    );
+
+    let mut probe_batch = ProbeBatch::new();
+    ProbeGenerator::generate(
+        &scene,
+        volume,
+        ProbeGenerationType::UniformFloor {
+            spacing: 2.0,
+            height: 1.5,
+        },
+        &mut probe_batch,
+    );
+
+    assert_eq!(probe_batch.num_probes(), 25);
+    for probe in probe_batch.probes() {
+        assert!((probe.position.y - 1.5).abs() < 1e-4);
+        assert!(probe.position.x.abs() <= 4.5 && probe.position.z.abs() <= 4.5);
+    }
+}
+
+#[test]
+fn neighborhood_weights() {
+    let mut probe_batch = ProbeBatch::new();
+    probe_batch.add_probe(Probe::new(Vec3::new(0.0, 1.0, 0.0), 2.0));
+    probe_batch.add_probe(Probe::new(Vec3::new(3.0, 1.0, 0.0), 2.0));
+    probe_batch.add_probe(Probe::new(Vec3::new(1.5, 1.0, 0.0), 2.0));
+    probe_batch.add_probe(Probe::new(Vec3::new(20.0, 1.0, 0.0), 2.0));
+
+    // Nothing can be found before committing.
+    assert!(
+        probe_batch
+            .find_neighborhood(None, Vec3::new(1.0, 1.0, 0.0))
+            .is_empty()
+    );
+
+    probe_batch.commit();
+
+    let neighborhood = probe_batch.find_neighborhood(None, Vec3::new(1.0, 1.0, 0.0));
+    let indices: Vec<usize> = neighborhood.neighbors.iter().map(|(i, _)| *i).collect();
+    assert_eq!(indices, vec![2, 0, 1]);
+
+    let total_weight: f32 = neighborhood.neighbors.iter().map(|(_, w)| w).sum();
+    assert!((total_weight - 1.0).abs() < 1e-5);
+    assert!(neighborhood.neighbors[0].1 > neighborhood.neighbors[1].1);
+
+    // A wall between the point and a probe hides that probe.
+    let scene = floor_scene();
+    let neighborhood = probe_batch.find_neighborhood(Some(&scene), Vec3::new(1.5, -0.5, 0.0));
+    assert!(neighborhood.is_empty());
+}
+
+#[test]
+fn removed_probes_are_not_found_before_commit() {
+    let mut probe_batch = ProbeBatch::new();
+    probe_batch.add_probe(Probe::new(Vec3::new(0.0, 1.0, 0.0), 2.0));
+    probe_batch.add_probe(Probe::new(Vec3::new(1.0, 1.0, 0.0), 2.0));
+    probe_batch.add_probe(Probe::new(Vec3::new(2.0, 1.0, 0.0), 2.0));
+    probe_batch.commit();
+
+    let point = Vec3::new(1.5, 1.0, 0.0);
+    assert_eq!(
+        probe_batch.find_neighborhood(None, point).neighbors.len(),
+        3
+    );
+
+    // The committed grid still refers to the last probe, which no longer exists.
+    probe_batch.remove_probe(2);
+    probe_batch.remove_probe(1);
+    assert!(probe_batch.find_neighborhood(None, point).is_empty());
+
+    probe_batch.commit();
+    let neighborhood = probe_batch.find_neighborhood(None, point);
+    assert_eq!(neighborhood.neighbors, vec![(0, 1.0)]);
+}
@@ -0,0 +1,79 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+mod common;
+
+use common::medium_without_absorption;
+use phonon::dsp::energy_field::{BIN_DURATION, EnergyField};
+use phonon::dsp::reverb_estimator::{Reverb, ReverbEstimator, ReverbFormula, RoomProperties};
+
+#[test]
+fn sabine_and_eyring() {
+    let room = RoomProperties {
+        volume: 100.0,
+        surface_area: 130.0,
+        absorption: [0.05, 0.2, 0.5],
+    };
+
+    let mut sabine = Reverb::default();
+    ReverbEstimator::estimate_from_room(
+        &room,
+        ReverbFormula::Sabine,
+        &medium_without_absorption(),
+        &mut sabine,
+    );
+
+    let mut eyring = Reverb::default();
+    ReverbEstimator::estimate_from_room(
+        &room,
+        ReverbFormula::Eyring,
+        &medium_without_absorption(),
+        &mut eyring,
+    );
+
+    // More absorption means shorter reverb times.
+    assert!(sabine.reverb_times[0] > sabine.reverb_times[1]);
+    assert!(sabine.reverb_times[1] > sabine.reverb_times[2]);
+
+    // Eyring always predicts a shorter reverb time than Sabine, more so for absorptive rooms.
+    for (eyring_time, sabine_time) in eyring.reverb_times.iter().zip(sabine.reverb_times) {
+        assert!(*eyring_time < sabine_time);
+    }
+
+    assert!((sabine.reverb_times[1] - 0.63).abs() < 0.01);
+}
+
+#[test]
+fn decay_curve_fitting() {
+    let reverb_times = [2.0, 1.0, 0.3];
+
+    let mut energy_field = EnergyField::new(3.0, 0);
+    for (band, reverb_time) in reverb_times.iter().enumerate() {
+        for bin in 0..energy_field.num_bins() {
+            let time = bin as f32 * BIN_DURATION;
+            energy_field.data[[0, band, bin]] = 10.0f32.powf(-6.0 * time / reverb_time);
+        }
+    }
+
+    let mut reverb = Reverb::default();
+    ReverbEstimator::estimate_from_energy_field(&energy_field, &mut reverb);
+
+    for (estimated, expected) in reverb.reverb_times.iter().zip(reverb_times) {
+        let error = (estimated - expected).abs() / expected;
+        assert!(error < 0.05, "{:?}", reverb.reverb_times);
+    }
+}
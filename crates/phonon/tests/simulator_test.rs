@@ -0,0 +1,578 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+mod common;
+
+use common::wall_mesh;
+use glam::Vec3;
+use phonon::models::occlusion::OcclusionModel;
+use phonon::models::propagation_medium::PropagationMedium;
+use phonon::models::transmission::TransmissionModel;
+use phonon::scene::Scene;
+use phonon::scene::coordinate_space::CoordinateSpace3f;
+use phonon::simulators::direct::{DirectSoundPath, OcclusionType};
+use phonon::simulators::simulator::{Listener, Simulator, Source, SourceSettings};
+use phonon::simulators::source_shape::{ShapeAttenuation, SourceShape};
+use std::sync::{Arc, Mutex};
+
+fn source_at(position: Vec3) -> Arc<Mutex<Source>> {
+    Arc::new(Mutex::new(Source::new(
+        CoordinateSpace3f::from_origin(position),
+        SourceSettings::default(),
+    )))
+}
+
+#[test]
+fn run_direct_updates_sources() {
+    let mut simulator = Simulator::new(16);
+    simulator.scene.add_static_mesh(Arc::new(wall_mesh()));
+    simulator.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, 2.0));
+
+    let visible = source_at(Vec3::new(1.0, 1.0, 4.0));
+    let occluded = source_at(Vec3::new(1.0, 1.0, -4.0));
+    simulator.add_source(visible.clone());
+    simulator.add_source(occluded.clone());
+
+    // Sources are only simulated after committing.
+    assert_eq!(simulator.num_sources(), 0);
+    simulator.commit();
+    assert_eq!(simulator.num_sources(), 2);
+
+    simulator.run_direct();
+
+    let visible_path = visible.lock().unwrap().listener_results[0].direct_sound_path;
+    assert_eq!(visible_path.occlusion, 1.0);
+    assert!((visible_path.distance_attenuation - 1.0 / 5.0f32.sqrt()).abs() < 1e-5);
+    assert_eq!(
+        occluded.lock().unwrap().listener_results[0]
+            .direct_sound_path
+            .occlusion,
+        0.0
+    );
+
+    // Moving a source does not require a commit.
+    occluded.lock().unwrap().coordinates =
+        CoordinateSpace3f::from_origin(Vec3::new(1.0, 6.0, -4.0));
+    simulator.run_direct();
+    assert_eq!(
+        occluded.lock().unwrap().listener_results[0]
+            .direct_sound_path
+            .occlusion,
+        1.0
+    );
+
+    simulator.remove_source(occluded);
+    simulator.commit();
+    assert_eq!(simulator.num_sources(), 1);
+}
+
+/// Sources along the top edge of the wall, partly occluded by it.
+fn sources_behind_wall() -> Vec<Arc<Mutex<Source>>> {
+    let settings = SourceSettings {
+        occlusion_type: OcclusionType::Volumetric,
+        num_occlusion_samples: 32,
+        ..Default::default()
+    };
+
+    (0..64)
+        .map(|i| {
+            let position = Vec3::new(i as f32 * 0.25 - 8.0, 1.5 + (i % 5) as f32 * 0.2, -3.0);
+            Arc::new(Mutex::new(Source::new(
+                CoordinateSpace3f::from_origin(position),
+                settings,
+            )))
+        })
+        .collect()
+}
+
+#[test]
+fn simulation_is_order_independent() {
+    let mut simulator = Simulator::new(32);
+    simulator.scene.add_static_mesh(Arc::new(wall_mesh()));
+    simulator.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, 2.0));
+
+    let sources = sources_behind_wall();
+    for source in &sources {
+        simulator.add_source(source.clone());
+    }
+    simulator.commit();
+    simulator.run_direct();
+
+    let results: Vec<DirectSoundPath> = sources
+        .iter()
+        .map(|source| source.lock().unwrap().listener_results[0].direct_sound_path)
+        .collect();
+
+    // Simulating each source on its own gives exactly the same results.
+    for (source, expected) in sources.iter().zip(&results) {
+        let mut single = Simulator::new(32);
+        single.scene.add_static_mesh(Arc::new(wall_mesh()));
+        single.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, 2.0));
+        single.add_source(source.clone());
+        single.commit();
+        single.run_direct();
+
+        assert_eq!(
+            source.lock().unwrap().listener_results[0].direct_sound_path,
+            *expected
+        );
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn simulation_is_independent_of_thread_count() {
+    let mut simulator = Simulator::new(32);
+    simulator.scene.add_static_mesh(Arc::new(wall_mesh()));
+    simulator.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, 2.0));
+    // Every run has to simulate the sources again.
+    simulator.cache_tolerances = None;
+
+    let sources = sources_behind_wall();
+    for source in &sources {
+        simulator.add_source(source.clone());
+    }
+    simulator.commit();
+
+    let run_on_threads = |num_threads: usize| -> Vec<DirectSoundPath> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap();
+        pool.install(|| simulator.run_direct());
+
+        sources
+            .iter()
+            .map(|source| source.lock().unwrap().listener_results[0].direct_sound_path)
+            .collect()
+    };
+
+    let single_threaded = run_on_threads(1);
+    assert_eq!(run_on_threads(8), single_threaded);
+    assert_eq!(run_on_threads(3), single_threaded);
+}
+
+#[test]
+fn unchanged_sources_are_cached() {
+    let mut simulator = Simulator::new(16);
+    let wall = Arc::new(wall_mesh());
+    simulator.scene.add_static_mesh(wall.clone());
+    simulator.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, 2.0));
+
+    let source = source_at(Vec3::new(1.0, 1.0, -4.0));
+    simulator.add_source(source.clone());
+    simulator.commit();
+    simulator.run_direct();
+    assert_eq!(
+        source.lock().unwrap().listener_results[0]
+            .direct_sound_path
+            .occlusion,
+        0.0
+    );
+
+    // Overwrite the result, so that we can tell whether the source was simulated again.
+    let set_marker = || {
+        source.lock().unwrap().listener_results[0]
+            .direct_sound_path
+            .occlusion = 0.5
+    };
+    let occlusion = || {
+        source.lock().unwrap().listener_results[0]
+            .direct_sound_path
+            .occlusion
+    };
+
+    set_marker();
+    simulator.run_direct();
+    assert_eq!(occlusion(), 0.5);
+
+    // Moving less than the tolerance keeps the cached result.
+    source.lock().unwrap().coordinates =
+        CoordinateSpace3f::from_origin(Vec3::new(1.0, 1.005, -4.0));
+    simulator.run_direct();
+    assert_eq!(occlusion(), 0.5);
+
+    // Moving further does not.
+    source.lock().unwrap().coordinates = CoordinateSpace3f::from_origin(Vec3::new(1.0, 1.1, -4.0));
+    simulator.run_direct();
+    assert_eq!(occlusion(), 0.0);
+
+    // Neither does a change to the scene.
+    set_marker();
+    simulator.scene.remove_static_mesh(wall);
+    simulator.commit();
+    simulator.run_direct();
+    assert_eq!(occlusion(), 1.0);
+
+    // Or an explicit invalidation.
+    set_marker();
+    source.lock().unwrap().invalidate_cache();
+    simulator.run_direct();
+    assert_eq!(occlusion(), 1.0);
+
+    // Without tolerances, every run simulates all sources.
+    set_marker();
+    simulator.cache_tolerances = None;
+    simulator.run_direct();
+    assert_eq!(occlusion(), 1.0);
+}
+
+#[test]
+fn progressive_occlusion_converges() {
+    let mut simulator = Simulator::new(64);
+    simulator.scene.add_static_mesh(Arc::new(wall_mesh()));
+    simulator.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, 2.0));
+
+    // A source peeking over the top of the wall is partially occluded.
+    let position = Vec3::new(0.5, 2.5, -2.0);
+    let reference = Arc::new(Mutex::new(Source::new(
+        CoordinateSpace3f::from_origin(position),
+        SourceSettings {
+            occlusion_type: OcclusionType::Volumetric,
+            num_occlusion_samples: 64,
+            ..Default::default()
+        },
+    )));
+    let progressive = Arc::new(Mutex::new(Source::new(
+        CoordinateSpace3f::from_origin(position),
+        SourceSettings {
+            occlusion_type: OcclusionType::ProgressiveVolumetric { smoothing: 0.9 },
+            num_occlusion_samples: 8,
+            ..Default::default()
+        },
+    )));
+
+    simulator.add_source(reference.clone());
+    simulator.add_source(progressive.clone());
+    simulator.commit();
+
+    for _ in 0..64 {
+        simulator.run_direct();
+    }
+
+    let expected = reference.lock().unwrap().listener_results[0]
+        .direct_sound_path
+        .occlusion;
+    let occlusion = progressive.lock().unwrap().listener_results[0]
+        .direct_sound_path
+        .occlusion;
+    assert!(expected > 0.1 && expected < 0.9);
+    assert!(
+        (occlusion - expected).abs() < 0.1,
+        "progressive {occlusion}, expected {expected}"
+    );
+}
+
+#[test]
+fn volumetric_listener() {
+    let mut simulator = Simulator::new(64);
+    simulator.scene.add_static_mesh(Arc::new(wall_mesh()));
+    simulator.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.7, 1.0));
+
+    let source = Arc::new(Mutex::new(Source::new(
+        CoordinateSpace3f::from_origin(Vec3::new(0.5, 1.7, -1.0)),
+        SourceSettings {
+            occlusion_type: OcclusionType::Volumetric,
+            occlusion_radius: 0.1,
+            num_occlusion_samples: 64,
+            ..Default::default()
+        },
+    )));
+    simulator.add_source(source.clone());
+    simulator.commit();
+
+    // A point listener just below the top of the wall cannot hear the source.
+    simulator.run_direct();
+    assert_eq!(
+        source.lock().unwrap().listener_results[0]
+            .direct_sound_path
+            .occlusion,
+        0.0
+    );
+
+    // A large listener sticks out above the wall.
+    simulator.listeners[0].radius = 1.0;
+    simulator.run_direct();
+    let occlusion = source.lock().unwrap().listener_results[0]
+        .direct_sound_path
+        .occlusion;
+    assert!(occlusion > 0.0 && occlusion < 1.0, "occlusion {occlusion}");
+}
+
+#[test]
+fn medium_determines_delay() {
+    let mut simulator = Simulator::new(16);
+    simulator.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::ZERO);
+
+    let source = source_at(Vec3::new(0.0, 0.0, -100.0));
+    simulator.add_source(source.clone());
+    simulator.commit();
+
+    simulator.run_direct();
+    let in_air = source.lock().unwrap().listener_results[0].direct_sound_path;
+    assert!((in_air.delay - 100.0 / 340.0).abs() < 1e-5);
+
+    // Sound travels more than four times as fast under water, and high frequencies carry much
+    // further.
+    simulator.set_medium(PropagationMedium::water());
+    simulator.run_direct();
+    let in_water = source.lock().unwrap().listener_results[0].direct_sound_path;
+    assert!((in_water.delay - 100.0 / 1482.0).abs() < 1e-5);
+    assert!(in_air.air_absorption[2] < 1e-6);
+    assert!(in_water.air_absorption[2] > 0.99);
+
+    // A source with its own air absorption model keeps it.
+    source.lock().unwrap().air_absorption_model = Some(Box::new(PropagationMedium::default()));
+    simulator.set_medium(PropagationMedium::water());
+    simulator.run_direct();
+    let own_model = source.lock().unwrap().listener_results[0].direct_sound_path;
+    assert_eq!(own_model.air_absorption, in_air.air_absorption);
+
+    // The speed of sound in air depends on its temperature.
+    let warm = PropagationMedium::air(20.0, 50.0);
+    assert!((warm.speed_of_sound - 343.2).abs() < 0.1);
+    assert!((warm.density - 1.204).abs() < 0.001);
+}
+
+#[test]
+fn line_source() {
+    let mut simulator = Simulator::new(16);
+    simulator.scene.add_static_mesh(Arc::new(wall_mesh()));
+    simulator.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, 2.0));
+
+    // A road behind the wall, that sticks out to the right of it.
+    let mut road = Source::new(
+        CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, -4.0)),
+        SourceSettings {
+            num_occlusion_samples: 64,
+            ..Default::default()
+        },
+    );
+    road.shape = SourceShape::Segment {
+        start: Vec3::new(-20.0, 0.0, 0.0),
+        end: Vec3::new(80.0, 0.0, 0.0),
+    };
+    let road = Arc::new(Mutex::new(road));
+
+    simulator.add_source(road.clone());
+    simulator.commit();
+    simulator.run_direct();
+
+    let (nearest, visible_part) = {
+        let road = road.lock().unwrap();
+        let path = road.listener_results[0].direct_sound_path;
+
+        // Distance is measured to the nearest point, which is straight behind the wall.
+        assert!((path.distance_attenuation - 1.0 / 6.0).abs() < 1e-4);
+        assert!((path.delay - 6.0 / 340.0).abs() < 1e-5);
+
+        // Only the part of the road that sticks out can be heard, so it seems to come from
+        // there.
+        assert!(path.occlusion > 0.0 && path.occlusion < 1.0);
+        assert!(road.listener_results[0].apparent_position.x > 30.0);
+
+        (path.distance_attenuation, path.occlusion)
+    };
+
+    // Every metre of the road adds to the level, so the whole road is louder than its nearest
+    // point. Along an infinite line the energy would be pi / 6 instead of 1 / 36.
+    let integral_with_density = |density: f32| {
+        road.lock().unwrap().settings.shape_attenuation = ShapeAttenuation::Integral { density };
+        simulator.run_direct();
+
+        let road = road.lock().unwrap();
+        assert_eq!(
+            road.listener_results[0].direct_sound_path.occlusion,
+            visible_part
+        );
+        road.listener_results[0]
+            .direct_sound_path
+            .distance_attenuation
+    };
+
+    let integral = integral_with_density(1.0);
+    assert!(integral > nearest);
+    assert!(integral < (std::f32::consts::PI / 6.0).sqrt());
+
+    // Four times the power per metre is twice the amplitude.
+    assert!((integral_with_density(4.0) - 2.0 * integral).abs() < 1e-5);
+}
+
+#[test]
+fn partly_hidden_shapes_are_diffracted() {
+    let mut simulator = Simulator::new(16);
+    simulator.scene.add_static_mesh(Arc::new(wall_mesh()));
+    simulator.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::new(11.0, 1.0, 2.0));
+
+    // The nearest point of the road is past the end of the wall, but most of the road is
+    // behind it.
+    let mut road = Source::new(
+        CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, -4.0)),
+        SourceSettings {
+            num_occlusion_samples: 64,
+            ..Default::default()
+        },
+    );
+    road.shape = SourceShape::Segment {
+        start: Vec3::new(-20.0, 0.0, 0.0),
+        end: Vec3::new(15.0, 0.0, 0.0),
+    };
+    let road = Arc::new(Mutex::new(road));
+
+    simulator.add_source(road.clone());
+    simulator.commit();
+    simulator.run_direct();
+
+    let path = road.lock().unwrap().listener_results[0].direct_sound_path;
+    assert!(path.occlusion < 0.5);
+    assert!(path.diffraction.iter().all(|&factor| factor > 0.0));
+}
+
+#[test]
+fn shape_geometry() {
+    let coordinates = CoordinateSpace3f::from_origin(Vec3::new(0.0, 0.0, -10.0));
+
+    let river = SourceShape::Polyline(vec![
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(10.0, 0.0, 0.0),
+        Vec3::new(10.0, 0.0, -30.0),
+    ]);
+    let closest = river.closest_point(&coordinates, Vec3::new(20.0, 5.0, -20.0));
+    assert!(closest.distance(Vec3::new(10.0, 0.0, -20.0)) < 1e-5);
+
+    // Samples are spread by length, so most lie on the longer second segment.
+    let samples = river.sample_points(&coordinates, 40);
+    assert_eq!(samples.len(), 40);
+    assert_eq!(samples.iter().filter(|sample| sample.z < -10.0).count(), 30);
+    assert_eq!(river.measure(), 40.0);
+
+    let waterfall = SourceShape::Rectangle {
+        half_extents: glam::Vec2::new(5.0, 10.0),
+    };
+    let closest = waterfall.closest_point(&coordinates, Vec3::new(20.0, 3.0, 0.0));
+    assert!(closest.distance(Vec3::new(5.0, 3.0, -10.0)) < 1e-5);
+    assert_eq!(waterfall.sample_points(&coordinates, 16).len(), 16);
+    assert_eq!(waterfall.measure(), 200.0);
+}
+
+#[test]
+fn multiple_listeners() {
+    let mut simulator = Simulator::new(16);
+    simulator.scene.add_static_mesh(Arc::new(wall_mesh()));
+
+    // One player on either side of the wall.
+    simulator.listeners = vec![
+        Listener::new(CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, 2.0))),
+        Listener::new(CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, -8.0))),
+    ];
+
+    let source = source_at(Vec3::new(1.0, 1.0, -4.0));
+    simulator.add_source(source.clone());
+    simulator.commit();
+    simulator.run_direct();
+
+    let paths: Vec<DirectSoundPath> = source
+        .lock()
+        .unwrap()
+        .listener_results
+        .iter()
+        .map(|result| result.direct_sound_path)
+        .collect();
+    assert_eq!(paths.len(), 2);
+    assert_eq!(paths[0].occlusion, 0.0);
+    assert_eq!(paths[1].occlusion, 1.0);
+    assert!(paths[0].delay > paths[1].delay);
+
+    // Played over shared speakers, the source is heard by both players equally.
+    let mixed = DirectSoundPath::mix([(paths[0], 1.0), (paths[1], 1.0)]);
+    assert_eq!(mixed.occlusion, 0.5);
+    // The sound arrives when it first reaches either player.
+    assert_eq!(mixed.delay, paths[1].delay);
+
+    // Or only by the second one.
+    let mixed = DirectSoundPath::mix([(paths[0], 0.0), (paths[1], 2.0)]);
+    assert_eq!(mixed, paths[1]);
+
+    // Removing a listener drops its results.
+    simulator.listeners.pop();
+    simulator.run_direct();
+    assert_eq!(source.lock().unwrap().listener_results.len(), 1);
+}
+
+/// A door that is either open or closed, regardless of the geometry.
+struct Door {
+    open: bool,
+}
+
+impl OcclusionModel for Door {
+    fn evaluate(
+        &self,
+        _scene: &Scene,
+        _listener: &CoordinateSpace3f,
+        _source: &CoordinateSpace3f,
+    ) -> f32 {
+        if self.open { 1.0 } else { 0.0 }
+    }
+}
+
+/// The same transmission for every wall, as tuned by a designer.
+struct FixedTransmission([f32; 3]);
+
+impl TransmissionModel for FixedTransmission {
+    fn evaluate(
+        &self,
+        _scene: &Scene,
+        _listener: &CoordinateSpace3f,
+        _source: &CoordinateSpace3f,
+        transmission: &mut [f32; 3],
+    ) {
+        *transmission = self.0;
+    }
+}
+
+#[test]
+fn custom_occlusion_and_transmission() {
+    let mut simulator = Simulator::new(16);
+    simulator.scene.add_static_mesh(Arc::new(wall_mesh()));
+    simulator.listeners[0].coordinates = CoordinateSpace3f::from_origin(Vec3::new(0.0, 1.0, 2.0));
+
+    // Nothing is in between, but the door is closed.
+    let source = source_at(Vec3::new(0.0, 5.0, -4.0));
+    source.lock().unwrap().occlusion_model = Some(Box::new(Door { open: false }));
+    source.lock().unwrap().transmission_model = Some(Box::new(FixedTransmission([0.5, 0.3, 0.1])));
+    simulator.add_source(source.clone());
+    simulator.commit();
+    simulator.run_direct();
+
+    let path = source.lock().unwrap().listener_results[0].direct_sound_path;
+    assert_eq!(path.occlusion, 0.0);
+    assert_eq!(path.transmission, [0.5, 0.3, 0.1]);
+
+    // Opening the door is not something the cache can see.
+    source.lock().unwrap().occlusion_model = Some(Box::new(Door { open: true }));
+    source.lock().unwrap().invalidate_cache();
+    simulator.run_direct();
+    let path = source.lock().unwrap().listener_results[0].direct_sound_path;
+    assert_eq!(path.occlusion, 1.0);
+
+    // Without custom models the geometry decides again.
+    source.lock().unwrap().occlusion_model = None;
+    source.lock().unwrap().coordinates = CoordinateSpace3f::from_origin(Vec3::new(1.0, 1.0, -4.0));
+    simulator.run_direct();
+    let path = source.lock().unwrap().listener_results[0].direct_sound_path;
+    assert_eq!(path.occlusion, 0.0);
+}
@@ -0,0 +1,124 @@
+//
+// Copyright 2017-2023 Valve Corporation.
+// Copyright 2024 phonon_rs contributors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use glam::Vec3;
+use phonon::dsp::audio_buffer::AudioSettings;
+use phonon::dsp::speaker_layout::SpeakerLayoutType;
+use phonon::effects::binaural::{BinauralEffect, BinauralEffectParameters};
+use phonon::effects::panning::{PanningEffect, PanningEffectParameters};
+use phonon::effects::spread::spread_from_radius;
+
+const SAMPLING_RATE: u32 = 48_000;
+const FRAME_SIZE: usize = 480;
+
+#[test]
+fn spread_from_source_radius() {
+    assert_eq!(spread_from_radius(0.0, 10.0), 0.0);
+    assert!((spread_from_radius(1.0, 2.0) - 60.0).abs() < 1e-3);
+    assert_eq!(spread_from_radius(2.0, 1.0), 360.0);
+
+    // A source gets narrower as it moves away.
+    assert!(spread_from_radius(1.0, 10.0) < spread_from_radius(1.0, 5.0));
+}
+
+/// Returns the left and right gains of a stereo panned source with a constant input.
+fn stereo_gains(parameters: PanningEffectParameters) -> (f32, f32) {
+    let mut effect = PanningEffect::new(SpeakerLayoutType::Stereo);
+    let input = vec![1.0; FRAME_SIZE];
+    let mut left = vec![0.0; FRAME_SIZE];
+    let mut right = vec![0.0; FRAME_SIZE];
+
+    // The first frame fades in from the default direction.
+    for _ in 0..2 {
+        effect.apply(parameters, &[&input], &mut [&mut left, &mut right]);
+    }
+
+    (left[FRAME_SIZE - 1], right[FRAME_SIZE - 1])
+}
+
+#[test]
+fn spread_widens_panning() {
+    let point = PanningEffectParameters {
+        direction: Vec3::X,
+        spread: 0.0,
+    };
+    let (left, right) = stereo_gains(point);
+    assert!(left.abs() < 1e-4);
+    assert!((right - 1.0).abs() < 1e-4);
+
+    let wide = PanningEffectParameters {
+        spread: 120.0,
+        ..point
+    };
+    let (wide_left, wide_right) = stereo_gains(wide);
+    assert!(wide_left > 0.1);
+    assert!(wide_right > wide_left);
+
+    // The power is preserved.
+    assert!((wide_left.powi(2) + wide_right.powi(2) - 1.0).abs() < 1e-3);
+
+    // A source that surrounds the listener is equally loud on both sides.
+    let (surround_left, surround_right) = stereo_gains(PanningEffectParameters {
+        spread: 360.0,
+        ..point
+    });
+    assert!((surround_left - surround_right).abs() < 0.05);
+}
+
+/// Returns the ratio of the energy in the right ear to that in the left ear for noise coming
+/// from the right.
+fn interaural_level_ratio(spread: f32) -> f32 {
+    let mut effect = BinauralEffect::new(AudioSettings::new(SAMPLING_RATE, FRAME_SIZE));
+    let parameters = BinauralEffectParameters {
+        direction: Vec3::new(1.0, 0.0, 0.0),
+        spread,
+    };
+
+    let mut state = 1u32;
+    let mut left_energy = 0.0;
+    let mut right_energy = 0.0;
+
+    for _ in 0..20 {
+        let input: Vec<f32> = (0..FRAME_SIZE)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 8) as f32 / (1 << 24) as f32 - 0.5
+            })
+            .collect();
+        let mut left = vec![0.0; FRAME_SIZE];
+        let mut right = vec![0.0; FRAME_SIZE];
+
+        effect.apply(parameters, &[&input], &mut [&mut left, &mut right]);
+
+        left_energy += left.iter().map(|sample| sample * sample).sum::<f32>();
+        right_energy += right.iter().map(|sample| sample * sample).sum::<f32>();
+    }
+
+    right_energy / left_energy
+}
+
+#[test]
+fn spread_widens_binaural() {
+    let point = interaural_level_ratio(0.0);
+    let wide = interaural_level_ratio(180.0);
+    let surround = interaural_level_ratio(360.0);
+
+    assert!(point > 1.0);
+    assert!(wide < point);
+    assert!(wide > 1.0);
+    assert!(surround < wide);
+}